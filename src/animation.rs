@@ -0,0 +1,246 @@
+//! Sprite animation. Each sprite sheet is a stack of frame blocks, each block `SPRITE_SHEET_ROWS`
+//! rows of the direction variants laid out as in the first. Which blocks make up the animation for
+//! each `AnimationState`, and how fast they play, is read from a RON file per sheet in
+//! `ANIMATIONS_DIR`, so frames can be added by extending the image and listing them there.
+//!
+//! `set_segment_sprites` and the spawners pick the direction variant, the `Animation::base` index,
+//! and `animate_sprites` adds the offset of the current frame's block.
+//!
+//! Whatever dies leaves a `Corpse` sprite behind in its place, which plays the Dying clip and is
+//! cleared away once that has finished.
+
+use std::{collections::HashMap, fs};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sprites::SpriteSheets, tween::Tween, Position, SPRITE_SHEET_COLUMNS, SPRITE_SHEET_ROWS,
+};
+
+pub const ANIMATIONS_DIR: &str = "assets/animations";
+const CORPSE_Z: f32 = -0.1; // Under the creatures, which are at 0
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum AnimationState {
+    Idle,
+    Moving,
+    Eating,
+    Attacking,
+    Dying,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnimationClip {
+    pub frames: Vec<usize>, // Frame blocks, in the order they play
+    pub fps: f32,
+    pub looping: bool, // Clips that don't loop go back to Idle when they finish
+}
+impl Default for AnimationClip {
+    fn default() -> Self {
+        AnimationClip {
+            frames: vec![0],
+            fps: 1.0,
+            looping: true,
+        }
+    }
+}
+impl AnimationClip {
+    fn duration(&self) -> f32 {
+        self.frames.len() as f32 / self.fps
+    }
+    fn frame(&self, elapsed: f32) -> usize {
+        let i = (elapsed * self.fps) as usize;
+        let i = if self.looping {
+            i % self.frames.len()
+        } else {
+            i.min(self.frames.len() - 1)
+        };
+        self.frames[i]
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SheetAnimations {
+    pub blocks: usize, // Frame blocks in the image
+    pub clips: HashMap<AnimationState, AnimationClip>,
+}
+impl Default for SheetAnimations {
+    fn default() -> Self {
+        SheetAnimations {
+            blocks: 1,
+            clips: HashMap::new(),
+        }
+    }
+}
+impl SheetAnimations {
+    /// Reads the animations at `path`. A missing or broken file leaves the sheet with a single
+    /// still frame for every state, and a broken clip leaves its state with one.
+    pub fn load(path: &str) -> SheetAnimations {
+        let mut animations = match fs::read_to_string(path) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+                warn!(target: "render", "Couldn't parse animations {}, using defaults: {}", path, e);
+                SheetAnimations::default()
+            }),
+            Err(e) => {
+                warn!(target: "render", "Couldn't read animations {}, using defaults: {}", path, e);
+                SheetAnimations::default()
+            }
+        };
+        if animations.blocks == 0 {
+            warn!(target: "render", "{} has no frame blocks, using one", path);
+            animations.blocks = 1;
+        }
+        let blocks = animations.blocks;
+        animations.clips.retain(|state, clip| {
+            if clip.frames.is_empty() || clip.fps <= 0.0 {
+                warn!(
+                    target: "render",
+                    "{} {:?} animation has no frames or no speed, using a still frame",
+                    path, state
+                );
+                return false;
+            }
+            if let Some(frame) = clip.frames.iter().find(|frame| **frame >= blocks) {
+                warn!(
+                    target: "render",
+                    "{} {:?} animation uses frame {}, but the sheet has only {}, using a still frame",
+                    path, state, frame, blocks
+                );
+                return false;
+            }
+            true
+        });
+        animations
+    }
+    pub fn rows(&self) -> usize {
+        self.blocks * SPRITE_SHEET_ROWS
+    }
+    fn clip(&self, state: AnimationState) -> AnimationClip {
+        self.clips.get(&state).cloned().unwrap_or_default()
+    }
+}
+
+/// Which sheet in `SpriteSheets` an animated sprite uses.
+#[derive(Clone, Copy, Debug)]
+pub enum Sheet {
+    Berry,
+    Mongoose,
+    Rat,
+    Snake,
+}
+
+#[derive(Component)]
+pub struct Animation {
+    pub sheet: Sheet,
+    pub base: usize, // Index of the direction variant in the first frame block
+    state: AnimationState,
+    elapsed: f32,
+}
+impl Animation {
+    pub fn new(sheet: Sheet) -> Animation {
+        Animation {
+            sheet,
+            base: 0,
+            state: AnimationState::Idle,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/// What's left of something that died, for as long as its Dying clip plays.
+#[derive(Component)]
+pub struct Corpse {
+    timer: Timer,
+}
+
+/// Leaves a corpse at `position` showing `animation`'s sprite, dying.
+pub fn spawn_corpse(
+    commands: &mut Commands,
+    sprite_sheets: &SpriteSheets,
+    animation: &Animation,
+    position: Position,
+) {
+    let sheet = sprite_sheets.get(animation.sheet);
+    let duration = sheet.animations.clip(AnimationState::Dying).duration();
+    let (mut sprite, texture_atlas, _) = sheet.sprite();
+    sprite.transform.translation.z = CORPSE_Z;
+    commands.spawn((
+        sprite,
+        texture_atlas,
+        Animation {
+            sheet: animation.sheet,
+            base: animation.base,
+            state: AnimationState::Dying,
+            elapsed: 0.0,
+        },
+        Corpse {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        },
+        position,
+    ));
+}
+
+pub fn clear_corpses(
+    mut commands: Commands,
+    mut corpses: Query<(Entity, &mut Corpse)>,
+    time: Res<Time>,
+) {
+    for (entity, mut corpse) in &mut corpses {
+        if corpse.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Plays `state` on `entity` from the start. Idle and Moving are chosen automatically, so this is
+/// for the others.
+#[derive(Event)]
+pub struct PlayAnimation {
+    pub entity: Entity,
+    pub state: AnimationState,
+}
+
+pub fn play_animations(
+    mut reader: EventReader<PlayAnimation>,
+    mut animations: Query<&mut Animation>,
+) {
+    for event in reader.read() {
+        if let Ok(mut animation) = animations.get_mut(event.entity) {
+            animation.state = event.state;
+            animation.elapsed = 0.0;
+        }
+    }
+}
+
+pub fn animate_sprites(
+    time: Res<Time>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut sprites: Query<(&mut Animation, &mut TextureAtlas, Option<&Tween>)>,
+) {
+    for (mut animation, mut texture_atlas, tween) in &mut sprites {
+        let sheet = &sprite_sheets.get(animation.sheet).animations;
+        animation.elapsed += time.delta_seconds();
+        let moving = tween.is_some_and(Tween::moving);
+        let state = match animation.state {
+            AnimationState::Idle | AnimationState::Moving if moving => AnimationState::Moving,
+            AnimationState::Idle | AnimationState::Moving => AnimationState::Idle,
+            state => state,
+        };
+        let mut clip = sheet.clip(state);
+        if !clip.looping && animation.elapsed >= clip.duration() && state != AnimationState::Dying {
+            // Hold the last frame of dying, go back to idling from anything else
+            clip = sheet.clip(AnimationState::Idle);
+            animation.state = AnimationState::Idle;
+        } else if state != animation.state {
+            animation.state = state;
+        }
+        let block = clip.frame(animation.elapsed);
+        let index = animation.base + block * SPRITE_SHEET_ROWS * SPRITE_SHEET_COLUMNS;
+        if texture_atlas.index != index {
+            texture_atlas.index = index;
+        }
+    }
+}
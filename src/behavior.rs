@@ -0,0 +1,329 @@
+//! Behavior trees. What a rat or snake does is decided by a tree of nodes read from a RON file per
+//! species in `BEHAVIORS_DIR`. The tree is ticked from the root every time a creature is between
+//! steps, so a branch higher up can interrupt whatever a lower one was doing. The actions work by
+//! setting the creature's `AI` target and path, which the movement systems then follow.
+//!
+//! Actions that choose a target keep it on later ticks for as long as the same node is the one
+//! that chose it, so walking somewhere doesn't mean choosing it again at every step.
+
+use std::{collections::HashMap, fs};
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    choose_flee_position, choose_random_unocc, distance, metabolism::Metabolism,
+    utility::Surroundings, utility::UtilityWeights, within_reach, Arena, Position, Target, AI,
+};
+
+pub const BEHAVIORS_DIR: &str = "assets/behaviors";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum Food {
+    Berry,
+    Rat,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Node {
+    // Composites
+    Sequence(Vec<Node>), // Ticks its children in order until one doesn't succeed
+    Selector(Vec<Node>), // Ticks its children in order until one doesn't fail
+    // Decorators
+    Invert(Box<Node>),  // Swaps success and failure
+    Succeed(Box<Node>), // Turns failure into success
+    // Conditions
+    Threatened,  // A predator is within the species' fear radius
+    Hungry(f32), // Hunger is at least this
+    HasTarget,
+    // Actions
+    FindNearest(Food), // Targets the nearest food of this kind within reach
+    Wander,            // Targets a random empty cell nearby
+    Flee,              // Targets the safest cell nearby
+    Decide,            // Lets the species' utility weights choose a target, or nothing
+    PathTo,            // Runs until the target is reached, failing if there's no way there
+    Wait(f32),         // Runs for this many seconds
+}
+impl Default for Node {
+    fn default() -> Self {
+        Node::Selector(vec![
+            Node::Sequence(vec![Node::Threatened, Node::Flee, Node::PathTo]),
+            Node::Sequence(vec![Node::Decide, Node::Succeed(Box::new(Node::PathTo))]),
+        ])
+    }
+}
+impl Node {
+    /// Reads the tree at `path`. A missing or broken file leaves the species with the default tree.
+    pub fn load(path: &str) -> Node {
+        match fs::read_to_string(path) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+                warn!(target: "ai", "Couldn't parse behavior tree {}, using the default: {}", path, e);
+                Node::default()
+            }),
+            Err(e) => {
+                warn!(target: "ai", "Couldn't read behavior tree {}, using the default: {}", path, e);
+                Node::default()
+            }
+        }
+    }
+    /// Number of nodes in this subtree. Nodes are identified by their index in a pre-order walk
+    /// of the tree, so skipped children still have to be counted.
+    fn size(&self) -> usize {
+        match self {
+            Node::Sequence(children) | Node::Selector(children) => {
+                1 + children.iter().map(Node::size).sum::<usize>()
+            }
+            Node::Invert(child) | Node::Succeed(child) => 1 + child.size(),
+            _ => 1,
+        }
+    }
+    fn label(&self) -> String {
+        match self {
+            Node::Sequence(_) => "Sequence".into(),
+            Node::Selector(_) => "Selector".into(),
+            Node::Invert(_) => "Invert".into(),
+            Node::Succeed(_) => "Succeed".into(),
+            leaf => format!("{:?}", leaf),
+        }
+    }
+    /// Ticks the whole tree for one creature.
+    pub fn run(&self, mind: &mut Mind) -> Status {
+        let mut trail = Vec::new();
+        let mut visited = Vec::new();
+        let status = self.tick(0, mind, &mut trail, &mut visited);
+        mind.ai.behavior.waits.retain(|id, _| visited.contains(id));
+        status
+    }
+    fn tick(
+        &self,
+        id: usize,
+        mind: &mut Mind,
+        trail: &mut Vec<String>,
+        visited: &mut Vec<usize>,
+    ) -> Status {
+        visited.push(id);
+        trail.push(self.label());
+        let status = match self {
+            Node::Sequence(children) | Node::Selector(children) => {
+                let carry_on = if let Node::Sequence(_) = self {
+                    Status::Success
+                } else {
+                    Status::Failure
+                };
+                let mut child_id = id + 1;
+                let mut status = carry_on;
+                for child in children {
+                    status = child.tick(child_id, mind, trail, visited);
+                    if status != carry_on {
+                        break;
+                    }
+                    child_id += child.size();
+                }
+                status
+            }
+            Node::Invert(child) => match child.tick(id + 1, mind, trail, visited) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            Node::Succeed(child) => match child.tick(id + 1, mind, trail, visited) {
+                Status::Running => Status::Running,
+                _ => Status::Success,
+            },
+            leaf => {
+                let status = leaf.act(id, mind);
+                mind.ai.behavior.active = trail.join(" > ");
+                status
+            }
+        };
+        trail.pop();
+        status
+    }
+    fn act(&self, id: usize, mind: &mut Mind) -> Status {
+        let Mind { ai, position, .. } = mind;
+        let succeed_if = |condition: bool| {
+            if condition {
+                Status::Success
+            } else {
+                Status::Failure
+            }
+        };
+        match self {
+            Node::Threatened => succeed_if(mind.weights.threat(mind.nearest_predator) > 0.0),
+            Node::Hungry(threshold) => succeed_if(mind.metabolism.hunger >= *threshold),
+            Node::HasTarget => succeed_if(ai.target.is_some()),
+            Node::FindNearest(food) => {
+                let candidates = match food {
+                    Food::Berry => mind.berries,
+                    Food::Rat => mind.rats,
+                };
+                if ai.behavior.chosen_by == Some(id) {
+                    if let Some(Target::Entity(entity)) = ai.target {
+                        if candidates.iter().any(|(e, _)| *e == entity) {
+                            return Status::Success;
+                        }
+                    }
+                }
+                let found = within_reach(position, candidates)
+                    .into_iter()
+                    .min_by_key(|(_, p)| distance(position, p));
+                match found {
+                    Some((entity, _)) => {
+                        mind.choose(id, Some(Target::Entity(entity)));
+                        Status::Success
+                    }
+                    None => Status::Failure,
+                }
+            }
+            Node::Wander | Node::Flee => {
+                if ai.behavior.chosen_by == Some(id) && !ai.path.is_empty() {
+                    return Status::Success;
+                }
+                let found = if let Node::Wander = self {
+                    choose_random_unocc(position, mind.arena, mind.rng)
+                } else {
+                    choose_flee_position(
+                        position,
+                        mind.predators,
+                        mind.weights.exit_preference,
+                        mind.arena,
+                    )
+                };
+                match found {
+                    Some(p) => {
+                        mind.choose(id, Some(Target::Position(p)));
+                        Status::Success
+                    }
+                    None => Status::Failure,
+                }
+            }
+            Node::Decide => {
+                // Stick with the decision until it's time to think again
+                if ai.behavior.chosen_by == Some(id) && !ai.plan_timer.finished() {
+                    return Status::Success;
+                }
+                ai.plan_timer.reset();
+                // Creatures that have had enough to eat don't go looking for more
+                let (berries, rats) = if mind.metabolism.satiated() {
+                    (Vec::new(), Vec::new())
+                } else {
+                    (
+                        within_reach(position, mind.berries),
+                        within_reach(position, mind.rats),
+                    )
+                };
+                let surroundings = Surroundings {
+                    position: **position,
+                    hunger: mind.metabolism.hunger,
+                    berries: &berries,
+                    rats: &rats,
+                    nearest_predator: mind.nearest_predator,
+                    wander: choose_random_unocc(position, mind.arena, mind.rng),
+                    flee: choose_flee_position(
+                        position,
+                        mind.predators,
+                        mind.weights.exit_preference,
+                        mind.arena,
+                    ),
+                };
+                let choice = mind.weights.choose(&surroundings, mind.rng);
+                debug!(target: "ai", ?choice, "Chose what to do");
+                mind.choose(id, choice.target());
+                Status::Success
+            }
+            Node::PathTo => {
+                let goal = match ai.target {
+                    Some(Target::Entity(entity)) => mind
+                        .berries
+                        .iter()
+                        .chain(mind.rats)
+                        .find(|(e, _)| *e == entity)
+                        .map(|(_, p)| *p),
+                    Some(Target::Position(p)) => Some(p),
+                    None => return Status::Failure,
+                };
+                let Some(goal) = goal else {
+                    debug!(target: "ai", target = ?ai.target, "Lost its target");
+                    ai.abandon_target();
+                    return Status::Failure;
+                };
+                if **position == goal {
+                    return Status::Success;
+                }
+                if ai.path.back() == Some(&goal) {
+                    return Status::Running;
+                }
+                ai.abandon_path();
+                ai.plan_path(position, &goal, mind.arena);
+                trace!(target: "ai", target = ?ai.target, path = ?ai.path, "Planned a path");
+                if ai.path.is_empty() {
+                    Status::Failure
+                } else {
+                    Status::Running
+                }
+            }
+            Node::Wait(seconds) => {
+                let start = *ai.behavior.waits.entry(id).or_insert(mind.now);
+                if mind.now - start >= *seconds {
+                    ai.behavior.waits.remove(&id);
+                    Status::Success
+                } else {
+                    Status::Running
+                }
+            }
+            _ => unreachable!("{:?} is not a leaf", self),
+        }
+    }
+}
+
+/// Where a creature is in its tree, kept in its `AI`.
+#[derive(Default)]
+pub struct BehaviorState {
+    pub active: String,         // The nodes from the root to the last leaf ticked
+    chosen_by: Option<usize>,   // The node that chose the current target
+    waits: HashMap<usize, f32>, // When each running Wait node started
+}
+
+#[derive(Resource)]
+pub struct Behaviors {
+    pub rat: Node,
+    pub snake: Node,
+}
+impl Behaviors {
+    pub fn load(dir: &str) -> Behaviors {
+        Behaviors {
+            rat: Node::load(&format!("{}/rat.ron", dir)),
+            snake: Node::load(&format!("{}/snake.ron", dir)),
+        }
+    }
+}
+
+/// Everything a tree can see and change while it's ticked for one creature.
+pub struct Mind<'a> {
+    pub ai: &'a mut AI,
+    pub metabolism: &'a Metabolism,
+    pub position: &'a Position,
+    pub weights: &'a UtilityWeights,
+    pub berries: &'a [(Entity, Position)],
+    pub rats: &'a [(Entity, Position)],
+    pub predators: &'a [Position],
+    pub nearest_predator: Option<i32>,
+    pub arena: &'a mut Arena,
+    pub rng: &'a mut StdRng,
+    pub now: f32, // Seconds of play, which stop while paused, for timing waits
+}
+impl Mind<'_> {
+    fn choose(&mut self, id: usize, target: Option<Target>) {
+        self.ai.abandon_target();
+        self.ai.target = target;
+        self.ai.behavior.chosen_by = Some(id);
+    }
+}
@@ -0,0 +1,212 @@
+//! Bushes and the berries on them. The level puts bushes in the arena, which stand in the way like
+//! any other occupant and every so often grow a berry in an empty cell next to them. Berries start
+//! unripe, ripen, then rot, and are worth a different amount of food at each stage. A berry that's
+//! rotted for long enough falls apart on its own.
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    gameplay::Spawned,
+    reproduction::free_neighbour,
+    spawn_berry,
+    sprites::{ground_sprite, SpriteSheets},
+    Arena, DeathEvent, GameRng, Occupancy, Position, ARENA_HEIGHT, ARENA_WIDTH,
+};
+
+const BUSH_COLOR: Color = Color::DARK_GREEN;
+const BUSH_SIZE: f32 = 0.9; // Of a tile
+const BUSH_Z: f32 = -0.5; // Under the creatures, which are at 0
+
+// Tints over the berry sprite
+const UNRIPE_TINT: Color = Color::rgb(0.6, 1.0, 0.6);
+const RIPE_TINT: Color = Color::WHITE;
+const ROTTING_TINT: Color = Color::rgb(0.5, 0.4, 0.3);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Ripeness {
+    Unripe,
+    Ripe,
+    Rotting,
+}
+impl Ripeness {
+    fn next(self) -> Option<Ripeness> {
+        match self {
+            Ripeness::Unripe => Some(Ripeness::Ripe),
+            Ripeness::Ripe => Some(Ripeness::Rotting),
+            Ripeness::Rotting => None,
+        }
+    }
+    fn tint(self) -> Color {
+        match self {
+            Ripeness::Unripe => UNRIPE_TINT,
+            Ripeness::Ripe => RIPE_TINT,
+            Ripeness::Rotting => ROTTING_TINT,
+        }
+    }
+}
+
+/// How long a berry spends at one stage, and how filling it is meanwhile.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Stage {
+    pub duration: f32,  // Seconds
+    pub nutrition: f32, // Hunger taken away by eating it
+}
+impl Default for Stage {
+    fn default() -> Self {
+        Stage {
+            duration: 10.0,
+            nutrition: 0.3,
+        }
+    }
+}
+
+/// A berry's stage, and how long until the next.
+#[derive(Component)]
+pub struct Ripening {
+    pub ripeness: Ripeness,
+    timer: Timer,
+}
+impl Ripening {
+    pub fn new(ripeness: Ripeness, config: &Config) -> Ripening {
+        Ripening {
+            ripeness,
+            timer: Timer::from_seconds(config.berries.stage(ripeness).duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Grows berries around itself, up to a limit, one every `growth_period` seconds.
+#[derive(Component)]
+pub struct Bush {
+    berries: Vec<Entity>,
+    growth: Timer,
+}
+
+fn spawn_bush(commands: &mut Commands, arena: &mut Arena, config: &Config, position: Position) {
+    let bush = commands
+        .spawn((
+            ground_sprite(BUSH_COLOR, BUSH_SIZE, BUSH_Z),
+            Bush {
+                berries: Vec::new(),
+                growth: Timer::from_seconds(config.berries.growth_period, TimerMode::Repeating),
+            },
+            position,
+        ))
+        .id();
+    arena.set(position.x, position.y, Occupancy::Bush(bush));
+}
+
+/// Puts the level's bushes where it says, then any random ones wherever there's room.
+pub fn place_bushes(
+    mut commands: Commands,
+    mut arena: ResMut<Arena>,
+    mut game_rng: ResMut<GameRng>,
+    config: Res<Config>,
+) {
+    for position in config.berries.bushes.iter() {
+        if position.outside_arena() || arena.isset(position.x, position.y) {
+            warn!(target: "spawn", ?position, "No room for a bush");
+            continue;
+        }
+        spawn_bush(&mut commands, &mut arena, &config, *position);
+    }
+    let mut free = (0..ARENA_WIDTH)
+        .cartesian_product(0..ARENA_HEIGHT)
+        .filter(|(x, y)| !arena.isset(*x, *y))
+        .map(|(x, y)| Position { x, y })
+        .collect::<Vec<_>>();
+    let (placed, _) = free.partial_shuffle(&mut game_rng.rng, config.berries.random_bushes);
+    for position in placed.iter() {
+        spawn_bush(&mut commands, &mut arena, &config, *position);
+    }
+    if placed.len() < config.berries.random_bushes {
+        warn!(
+            target: "spawn",
+            missing = config.berries.random_bushes - placed.len(),
+            "No room for random bushes"
+        );
+    }
+    debug!(target: "spawn", level = ?config.berries.bushes, random = ?placed, "Placed bushes");
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn grow_berries(
+    mut commands: Commands,
+    mut bushes: Query<(&Position, &mut Bush)>,
+    berries: Query<(), With<Ripening>>,
+    mut arena: ResMut<Arena>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut game_rng: ResMut<GameRng>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
+    time: Res<Time>,
+) {
+    for (position, mut bush) in &mut bushes {
+        // Forget the berries that have been eaten or have rotted away
+        bush.berries.retain(|berry| berries.contains(*berry));
+        if bush.berries.len() >= config.berries.berries_per_bush {
+            bush.growth.reset();
+            continue;
+        }
+        if !bush.growth.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(cell) = free_neighbour(position, &arena, &mut game_rng.rng) else {
+            continue;
+        };
+        let berry = spawn_berry(
+            &mut commands,
+            &mut arena,
+            &sprite_sheets,
+            &mut spawned,
+            cell,
+        );
+        commands
+            .entity(berry)
+            .insert(Ripening::new(Ripeness::Unripe, &config));
+        bush.berries.push(berry);
+    }
+}
+
+/// Moves berries on to their next stage, and gets rid of the ones that have finished rotting.
+pub fn ripen_berries(
+    mut berries: Query<(Entity, &mut Ripening)>,
+    mut deaths: EventWriter<DeathEvent>,
+    config: Res<Config>,
+    time: Res<Time>,
+) {
+    for (berry, mut ripening) in &mut berries {
+        if !ripening.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        match ripening.ripeness.next() {
+            Some(ripeness) => {
+                trace!(target: "spawn", ?berry, ?ripeness, "Berry ripened");
+                *ripening = Ripening::new(ripeness, &config);
+            }
+            None => {
+                debug!(target: "spawn", ?berry, "Berry rotted away");
+                deaths.send(DeathEvent {
+                    entity: berry,
+                    by: None,
+                });
+            }
+        }
+    }
+}
+
+/// Tints each berry for its stage, whenever that changes.
+pub fn tint_berries(mut berries: Query<(&Ripening, &mut Sprite), Changed<Ripening>>) {
+    for (ripening, mut sprite) in &mut berries {
+        // Ripening changes on every tick of its timer, not just when the stage does
+        let tint = ripening.ripeness.tint();
+        if sprite.color != tint {
+            sprite.color = tint;
+        }
+    }
+}
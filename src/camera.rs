@@ -0,0 +1,126 @@
+//! The camera. World coordinates are fixed at `TILE_SIZE` per tile with the arena centred on the
+//! origin, and the camera fits the whole arena into the window at any size, keeping tiles square
+//! and filling the rest of the window with letterbox bars.
+//!
+//! In play, the mouse wheel zooms in, dragging with the right mouse button pans, Home resets the
+//! view and the `Follow` action, F unless rebound, toggles following the mongoose.
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    render::camera::ScalingMode,
+};
+
+use crate::{
+    input::{Action, ActionIntent},
+    settings::Settings,
+    GameState, Mongoose, Segmented, ARENA_HEIGHT, ARENA_WIDTH, BACKGROUND_COLOR, TILE_SIZE,
+};
+
+pub const LETTERBOX_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+const ZOOM_STEP: f32 = 1.25; // Scale change per notch of the mouse wheel
+const MIN_SCALE: f32 = 0.25; // Most zoomed in, at four times the size
+const MAX_SCALE: f32 = 1.0; // The whole arena
+
+#[derive(Component, Default)]
+pub struct GameCamera {
+    pan: Vec2, // Centre of the view when not following the mongoose
+}
+
+fn arena_size() -> Vec2 {
+    Vec2::new(
+        TILE_SIZE.x * ARENA_WIDTH as f32,
+        TILE_SIZE.y * ARENA_HEIGHT as f32,
+    )
+}
+
+pub fn spawn_camera(mut commands: Commands) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: arena_size().x,
+        min_height: arena_size().y,
+    };
+    commands.spawn((camera, GameCamera::default()));
+    // The arena's floor, so the letterboxing shows where it ends
+    commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: BACKGROUND_COLOR,
+            custom_size: Some(arena_size()),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, -1.0),
+        ..default()
+    });
+}
+
+/// Reads actions in every state, like the menus, but only toggles following in play.
+pub fn toggle_follow(
+    mut actions: EventReader<ActionIntent>,
+    state: Res<State<GameState>>,
+    mut settings: ResMut<Settings>,
+) {
+    let follow = actions.read().any(|intent| intent.action == Action::Follow);
+    if follow && *state.get() == GameState::Playing {
+        settings.follow_mongoose = !settings.follow_mongoose;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn control_camera(
+    mut wheel: EventReader<MouseWheel>,
+    mut motion: EventReader<MouseMotion>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    windows: Query<&Window>,
+    mongoose: Query<&Segmented, With<Mongoose>>,
+    transforms: Query<&Transform, Without<GameCamera>>,
+    mut cameras: Query<(&mut GameCamera, &mut Transform, &mut OrthographicProjection)>,
+) {
+    let Ok((mut camera, mut transform, mut projection)) = cameras.get_single_mut() else {
+        return;
+    };
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::Home) {
+        projection.scale = MAX_SCALE;
+        camera.pan = Vec2::ZERO;
+    }
+    for event in wheel.read() {
+        let scale = projection.scale * ZOOM_STEP.powf(-event.y.signum());
+        projection.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+    }
+    // World units per pixel, as of the last frame
+    let units_per_pixel = projection.area.width() / window.width();
+    for event in motion.read() {
+        if mouse_buttons.pressed(MouseButton::Right) {
+            camera.pan += Vec2::new(-event.delta.x, event.delta.y) * units_per_pixel;
+        }
+    }
+    let head = mongoose
+        .get_single()
+        .ok()
+        .and_then(|segmented| transforms.get(segmented.segments[0]).ok());
+    let centre = match head {
+        Some(head) if settings.follow_mongoose => head.translation.truncate(),
+        _ => camera.pan,
+    };
+    // Keep the view over the arena, unless it is wide enough to show all of it anyway
+    let half_view = projection.area.size() / 2.0;
+    let half_arena = arena_size() / 2.0;
+    let clamp = |centre: f32, half_view: f32, half_arena: f32| {
+        if half_view >= half_arena {
+            0.0
+        } else {
+            centre.clamp(half_view - half_arena, half_arena - half_view)
+        }
+    };
+    let centre = Vec2::new(
+        clamp(centre.x, half_view.x, half_arena.x),
+        clamp(centre.y, half_view.y, half_arena.y),
+    );
+    camera.pan = centre;
+    transform.translation.x = centre.x;
+    transform.translation.y = centre.y;
+}
@@ -0,0 +1,340 @@
+//! Game configuration, read from RON files at startup. Each file in `CONFIG_DIR` is a mode that can
+//! be picked from the main menu. Anything a file leaves out falls back to the defaults here.
+
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    berries::{Ripeness, Stage},
+    hud::HudItem,
+    level::LevelConfig,
+    metabolism::Appetite,
+    reproduction::Breeding,
+    visibility::Vision,
+    Position, Scoreboard, BERRY_SPAWN_PERIOD, RAT_SPAWN_PERIOD, SNAKE_SPAWN_PERIOD,
+};
+
+pub const CONFIG_DIR: &str = "assets/config";
+
+#[derive(Resource, Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub name: String,
+    pub seed: Option<u64>, // Random unless set
+    pub hud: HudConfig,
+    pub score: ScoreWeights,
+    pub metabolism: MetabolismConfig,
+    pub spawning: SpawnConfig,
+    pub reproduction: ReproductionConfig,
+    pub berries: BerryConfig,
+    pub level: LevelConfig,
+    pub vision: VisionConfig,
+    pub fog_of_war: bool, // Only show what the mongoose can see
+}
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            name: "Classic".into(),
+            seed: None,
+            hud: HudConfig::default(),
+            score: ScoreWeights::default(),
+            metabolism: MetabolismConfig::default(),
+            spawning: SpawnConfig::default(),
+            reproduction: ReproductionConfig::default(),
+            berries: BerryConfig::default(),
+            level: LevelConfig::default(),
+            vision: VisionConfig::default(),
+            fog_of_war: false,
+        }
+    }
+}
+impl Config {
+    pub fn load(path: &str) -> Config {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!(target: "config", "Couldn't read config {}, using defaults: {}", path, e);
+                return Config::default();
+            }
+        };
+        match ron::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(target: "config", "Couldn't parse config {}, using defaults: {}", path, e);
+                Config::default()
+            }
+        }
+    }
+}
+
+/// Every mode there is to choose from, in file name order, and the one chosen.
+#[derive(Resource)]
+pub struct Modes {
+    configs: Vec<Config>,
+    selected: usize,
+}
+impl Modes {
+    pub fn load(dir: &str) -> Modes {
+        let mut paths = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                warn!(target: "config", "Couldn't read config directory {}: {}", dir, e);
+                Vec::new()
+            }
+        };
+        paths.sort();
+        let mut configs = paths
+            .iter()
+            .map(|path| Config::load(&path.to_string_lossy()))
+            .collect::<Vec<_>>();
+        if configs.is_empty() {
+            configs.push(Config::default());
+        }
+        Modes {
+            configs,
+            selected: 0,
+        }
+    }
+    pub fn selected(&self) -> &Config {
+        &self.configs[self.selected]
+    }
+    pub fn cycle(&mut self, step: isize) {
+        let n = self.configs.len() as isize;
+        self.selected = (self.selected as isize + step).rem_euclid(n) as usize;
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.configs.iter().map(|config| config.name.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HudConfig {
+    pub font_size: f32,
+    pub items: Vec<HudItem>, // Rows of the HUD panel, top to bottom
+}
+impl Default for HudConfig {
+    fn default() -> HudConfig {
+        HudConfig {
+            font_size: 20.0,
+            items: vec![
+                HudItem::Score,
+                HudItem::Length,
+                HudItem::Hunger,
+                HudItem::Time,
+                HudItem::BerriesEatenByMongoose,
+                HudItem::RatsEatenByMongoose,
+                HudItem::BerriesEatenByRats,
+                HudItem::BerriesEatenBySnakes,
+                HudItem::RatsEatenBySnakes,
+                HudItem::RatsEscaped,
+                HudItem::Berries,
+                HudItem::Rats,
+                HudItem::Snakes,
+            ],
+        }
+    }
+}
+
+/// Points per counter in the `Scoreboard`, plus per mongoose segment and per second survived.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub berries_eaten_by_mongoose: f32,
+    pub berries_eaten_by_rats: f32,
+    pub berries_eaten_by_snakes: f32,
+    pub rats_eaten_by_mongoose: f32,
+    pub rats_eaten_by_snakes: f32,
+    pub rats_escaped: f32,
+    pub snakes_killed: f32,
+    pub mongoose_length: f32,
+    pub seconds: f32,
+}
+impl Default for ScoreWeights {
+    fn default() -> ScoreWeights {
+        ScoreWeights {
+            berries_eaten_by_mongoose: 1.0,
+            berries_eaten_by_rats: 0.0,
+            berries_eaten_by_snakes: 0.0,
+            rats_eaten_by_mongoose: 1.0,
+            rats_eaten_by_snakes: 0.0,
+            rats_escaped: 0.0,
+            snakes_killed: 0.0, // Nothing can kill a snake yet
+            mongoose_length: 0.0,
+            seconds: 0.0,
+        }
+    }
+}
+impl ScoreWeights {
+    pub fn score(&self, scoreboard: &Scoreboard, mongoose_length: usize, seconds: f32) -> f32 {
+        self.berries_eaten_by_mongoose * scoreboard.berries_eaten_by_mongoose as f32
+            + self.berries_eaten_by_rats * scoreboard.berries_eaten_by_rats as f32
+            + self.berries_eaten_by_snakes * scoreboard.berries_eaten_by_snakes as f32
+            + self.rats_eaten_by_mongoose * scoreboard.rats_eaten_by_mongoose as f32
+            + self.rats_eaten_by_snakes * scoreboard.rats_eaten_by_snakes as f32
+            + self.rats_escaped * scoreboard.rats_escaped as f32
+            + self.snakes_killed * scoreboard.snakes_killed as f32
+            + self.mongoose_length * mongoose_length as f32
+            + self.seconds * seconds
+    }
+}
+
+/// How quickly each species gets hungry, and how filling food is.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetabolismConfig {
+    pub mongoose: Appetite,
+    pub rat: Appetite,
+    pub snake: Appetite,
+    pub rat_nutrition: f32, // Hunger taken away by eating a rat, berries depend on their stage
+}
+impl Default for MetabolismConfig {
+    fn default() -> MetabolismConfig {
+        MetabolismConfig {
+            mongoose: Appetite {
+                hunger_rate: 0.05,
+                satiety: 0.0,
+                starvation_period: 5.0,
+            },
+            rat: Appetite {
+                hunger_rate: 0.02,
+                satiety: 0.2,
+                starvation_period: 10.0,
+            },
+            snake: Appetite {
+                hunger_rate: 0.03,
+                satiety: 0.3,
+                starvation_period: 10.0,
+            },
+            rat_nutrition: 0.6,
+        }
+    }
+}
+
+/// Seconds between things appearing out of nowhere, or `None` for never.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SpawnConfig {
+    pub berry_period: Option<f32>,
+    pub rat_period: Option<f32>,
+    pub snake_period: Option<f32>, // Snakes slither in from the edges
+}
+impl Default for SpawnConfig {
+    fn default() -> SpawnConfig {
+        SpawnConfig {
+            berry_period: Some(BERRY_SPAWN_PERIOD),
+            rat_period: Some(RAT_SPAWN_PERIOD),
+            snake_period: Some(SNAKE_SPAWN_PERIOD),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReproductionConfig {
+    pub rat: Breeding,
+    pub snake: Breeding,
+    pub rat_mating_range: i32, // How close two rats have to be to have young
+    pub hatch_time: f32,       // Seconds before an egg hatches
+}
+impl Default for ReproductionConfig {
+    fn default() -> ReproductionConfig {
+        ReproductionConfig {
+            rat: Breeding {
+                max_hunger: 0.3,
+                cooldown: 20.0,
+                hunger_cost: 0.3,
+                max_population: 12,
+            },
+            snake: Breeding {
+                max_hunger: 0.2,
+                cooldown: 30.0,
+                hunger_cost: 0.4,
+                max_population: 6,
+            },
+            rat_mating_range: 2,
+            hatch_time: 10.0,
+        }
+    }
+}
+
+/// Where bushes go, how they grow berries, and how the berries ripen and rot.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BerryConfig {
+    pub bushes: Vec<Position>,   // Where the level puts bushes
+    pub random_bushes: usize,    // How many more to put wherever there's room
+    pub growth_period: f32,      // Seconds between a bush's berries
+    pub berries_per_bush: usize, // A bush stops growing berries while it has this many
+    pub unripe: Stage,
+    pub ripe: Stage,
+    pub rotting: Stage, // Rotten berries disappear at the end of this
+}
+impl Default for BerryConfig {
+    fn default() -> BerryConfig {
+        BerryConfig {
+            bushes: vec![
+                Position { x: 4, y: 4 },
+                Position { x: 15, y: 4 },
+                Position { x: 4, y: 15 },
+                Position { x: 15, y: 15 },
+            ],
+            random_bushes: 0,
+            growth_period: 6.0,
+            berries_per_bush: 3,
+            unripe: Stage {
+                duration: 8.0,
+                nutrition: 0.1,
+            },
+            ripe: Stage {
+                duration: 15.0,
+                nutrition: 0.3,
+            },
+            rotting: Stage {
+                duration: 8.0,
+                nutrition: 0.05,
+            },
+        }
+    }
+}
+impl BerryConfig {
+    pub fn stage(&self, ripeness: Ripeness) -> &Stage {
+        match ripeness {
+            Ripeness::Unripe => &self.unripe,
+            Ripeness::Ripe => &self.ripe,
+            Ripeness::Rotting => &self.rotting,
+        }
+    }
+}
+
+/// How far, and how widely around the way it's facing, each species sees.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VisionConfig {
+    pub mongoose: Vision,
+    pub rat: Vision,
+    pub snake: Vision,
+}
+impl Default for VisionConfig {
+    fn default() -> VisionConfig {
+        VisionConfig {
+            mongoose: Vision {
+                range: 7.0,
+                cone: 200.0,
+            },
+            rat: Vision {
+                range: 6.0,
+                cone: 300.0,
+            },
+            snake: Vision {
+                range: 7.0,
+                cone: 120.0,
+            },
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! A debug overlay, toggled with F3. It draws every creature's planned path and target, outlines
+//! occupied cells in their occupant's colour, crosses out the pathfinding graph's missing edges,
+//! and describes the cell under the mouse cursor.
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::GameCamera, metabolism::Metabolism, Arena, Occupancy, Position, Rat, Segmented, Snake,
+    Target, AI, ARENA_HEIGHT, ARENA_WIDTH, TILE_SIZE,
+};
+
+const INSPECTOR_FONT_SIZE: f32 = 16.0;
+const INSPECTOR_PADDING: Val = Val::Px(5.0);
+const INSPECTOR_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+const RAT_PATH_COLOR: Color = Color::WHITE;
+const SNAKE_PATH_COLOR: Color = Color::YELLOW;
+const MISSING_EDGE_COLOR: Color = Color::RED;
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+pub struct Inspector;
+
+fn occupancy_color(occupancy: Occupancy) -> Color {
+    match occupancy {
+        Occupancy::Berry(_) => Color::FUCHSIA,
+        Occupancy::Mongoose(_) => Color::ORANGE,
+        Occupancy::Rat(_) => Color::GRAY,
+        Occupancy::Snake(_) => Color::DARK_GREEN,
+        Occupancy::Bush(_) => Color::LIME_GREEN,
+        Occupancy::Wall(_) => Color::WHITE,
+    }
+}
+
+pub fn spawn_inspector(mut commands: Commands) {
+    commands.spawn((
+        Inspector,
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: INSPECTOR_PADDING,
+                left: INSPECTOR_PADDING,
+                ..default()
+            },
+            background_color: INSPECTOR_BACKGROUND_COLOR.into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        }
+        .with_text_justify(JustifyText::Left),
+    ));
+}
+
+pub fn toggle_debug_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut inspectors: Query<&mut Visibility, With<Inspector>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.enabled = !overlay.enabled;
+    for mut visibility in &mut inspectors {
+        *visibility = if overlay.enabled {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn draw_debug_overlay(
+    mut gizmos: Gizmos,
+    arena: Res<Arena>,
+    creatures: Query<(&AI, Option<&Position>, Option<&Segmented>, Has<Rat>)>,
+    positions: Query<&Position>,
+) {
+    let tile = TILE_SIZE * 0.9;
+    for x in 0..ARENA_WIDTH {
+        for y in 0..ARENA_HEIGHT {
+            if let Some(occupancy) = arena.occ(x, y) {
+                let centre = Position { x, y }.translation().truncate();
+                gizmos.rect_2d(centre, 0.0, tile, occupancy_color(occupancy));
+            }
+        }
+    }
+    // Cells are cut off from their neighbours while occupied, so a missing edge is drawn as a
+    // short line across the border between the two
+    for x in -1..=ARENA_WIDTH {
+        for y in -1..=ARENA_HEIGHT {
+            for (neighbour_x, neighbour_y) in [(x + 1, y), (x, y + 1)] {
+                if neighbour_x > ARENA_WIDTH || neighbour_y > ARENA_HEIGHT {
+                    continue;
+                }
+                if arena.connected((x, y), (neighbour_x, neighbour_y)) {
+                    continue;
+                }
+                let a = Position { x, y }.translation().truncate();
+                let b = Position {
+                    x: neighbour_x,
+                    y: neighbour_y,
+                }
+                .translation()
+                .truncate();
+                let middle = (a + b) / 2.0;
+                let across = (b - a).perp() * 0.25;
+                gizmos.line_2d(middle - across, middle + across, MISSING_EDGE_COLOR);
+            }
+        }
+    }
+    for (ai, position, segmented, is_rat) in &creatures {
+        let Some(start) = position.copied().or(segmented.map(|s| s.head_position)) else {
+            continue;
+        };
+        let color = if is_rat {
+            RAT_PATH_COLOR
+        } else {
+            SNAKE_PATH_COLOR
+        };
+        gizmos.linestrip_2d(
+            std::iter::once(start)
+                .chain(ai.path.iter().copied())
+                .map(|p| p.translation().truncate()),
+            color,
+        );
+        let target = match &ai.target {
+            Some(Target::Position(position)) => Some(*position),
+            Some(Target::Entity(entity)) => positions.get(*entity).ok().copied(),
+            None => None,
+        };
+        if let Some(target) = target {
+            gizmos.circle_2d(target.translation().truncate(), TILE_SIZE.x * 0.3, color);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn inspect_cell(
+    overlay: Res<DebugOverlay>,
+    arena: Option<Res<Arena>>,
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    creatures: Query<(
+        &AI,
+        Option<&Metabolism>,
+        Option<&Position>,
+        Option<&Segmented>,
+        Has<Rat>,
+        Has<Snake>,
+    )>,
+    mut inspectors: Query<&mut Text, With<Inspector>>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let Ok(mut text) = inspectors.get_single_mut() else {
+        return;
+    };
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let cell = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+        .map(Position::from_translation);
+    let description = match (cell, arena) {
+        (None, _) => "Cursor outside the window".to_string(),
+        (Some(_), None) => "No game running".to_string(),
+        (Some(cell), Some(arena)) => {
+            let mut description = format!(
+                "Cell ({}, {}): {:?}, {} edges",
+                cell.x,
+                cell.y,
+                arena.occ(cell.x, cell.y),
+                arena.edge_count(cell.x, cell.y),
+            );
+            for (ai, metabolism, position, segmented, is_rat, is_snake) in &creatures {
+                let here =
+                    position == Some(&cell) || segmented.is_some_and(|s| s.head_position == cell);
+                if !here {
+                    continue;
+                }
+                let species = if is_rat {
+                    "Rat"
+                } else if is_snake {
+                    "Snake"
+                } else {
+                    "Creature"
+                };
+                description.push_str(&format!(
+                    "\n{} targeting {:?}, {} steps planned, next move in {:.2}s, replanning in {:.2}s, hunger {:.2}\n  {}",
+                    species,
+                    ai.target,
+                    ai.path.len(),
+                    ai.move_timer.remaining_secs(),
+                    ai.plan_timer.remaining_secs(),
+                    metabolism.map_or(0.0, |metabolism| metabolism.hunger),
+                    ai.behavior.active,
+                ));
+            }
+            description
+        }
+    };
+    *text = Text::from_section(
+        description,
+        TextStyle {
+            font_size: INSPECTOR_FONT_SIZE,
+            ..default()
+        },
+    );
+}
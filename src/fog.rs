@@ -0,0 +1,103 @@
+//! Fog of war, for game modes that turn it on. Only what the mongoose can see right now is shown as
+//! it is. Cells it has seen before are dimmed, showing the scenery but none of the creatures, berries
+//! or eggs there, and cells it has never seen are blacked out. It's drawn as a layer of squares over
+//! the arena, on top of where `transformation` puts everything.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    animation::Corpse, config::Config, level::Terrain, reproduction::Egg, sprites::ground_sprite,
+    visibility::Sight, Arena, Berry, Mongoose, Position, Rat, Segmented, Snake, ARENA_HEIGHT,
+    ARENA_WIDTH,
+};
+
+const FOG_COLOR: Color = Color::BLACK;
+const UNSEEN_ALPHA: f32 = 1.0;
+const REMEMBERED_ALPHA: f32 = 0.6;
+const FOG_Z: f32 = 5.0; // Over the creatures, which are at 0
+
+#[derive(Component)]
+pub struct FogCell;
+
+/// The cells the mongoose has seen at some point this game.
+#[derive(Resource, Default)]
+pub struct Fog {
+    remembered: HashSet<Position>,
+}
+
+/// Covers the arena in fog if the game mode has it, and clears away any left from the last game
+/// otherwise.
+pub fn spawn_fog(mut commands: Commands, config: Res<Config>) {
+    if !config.fog_of_war {
+        commands.remove_resource::<Fog>();
+        return;
+    }
+    for x in 0..ARENA_WIDTH {
+        for y in 0..ARENA_HEIGHT {
+            commands.spawn((
+                ground_sprite(FOG_COLOR.with_a(UNSEEN_ALPHA), 1.0, FOG_Z),
+                FogCell,
+                Position { x, y },
+            ));
+        }
+    }
+    commands.insert_resource(Fog::default());
+}
+
+/// Lifts the fog where the mongoose can see, and hides everything that lives and moves where it
+/// can't.
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn update_fog(
+    mut fog: ResMut<Fog>,
+    mongoose: Query<&Segmented, With<Mongoose>>,
+    positions: Query<&Position>,
+    arena: Res<Arena>,
+    terrain: Res<Terrain>,
+    config: Res<Config>,
+    mut cells: Query<(&Position, &mut Sprite), With<FogCell>>,
+    mut things: Query<
+        (&Position, &mut Visibility),
+        (
+            Or<(With<Rat>, With<Snake>, With<Berry>, With<Egg>, With<Corpse>)>,
+            Without<FogCell>,
+        ),
+    >,
+) {
+    // Once the mongoose is dead, the fog stays as it last saw things
+    let Ok(segmented) = mongoose.get_single() else {
+        return;
+    };
+    let sight = Sight::new(
+        &arena,
+        &terrain,
+        &segmented.head_position,
+        segmented.facing(&positions),
+        &config.vision.mongoose,
+    );
+    for (position, mut sprite) in &mut cells {
+        let alpha = if sight.sees(position) {
+            fog.remembered.insert(*position);
+            0.0
+        } else if fog.remembered.contains(position) {
+            REMEMBERED_ALPHA
+        } else {
+            UNSEEN_ALPHA
+        };
+        if sprite.color.a() != alpha {
+            sprite.color.set_a(alpha);
+        }
+    }
+    for (position, mut visibility) in &mut things {
+        let shown = if sight.spots(&terrain, position) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Gameplay events, one type per kind of thing that can happen in a game. The systems that make
+//! things happen only send these; the scoreboard is kept from them, and each game's events can be
+//! written to a JSON-lines file for analysis.
+//!
+//! Creatures and berries are named by their `Occupancy`, which carries both what they are and
+//! their entity.
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{settings::Settings, GameClock, Occupancy, Position, Scoreboard, Target};
+
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct Ate {
+    pub eater: Occupancy,
+    pub food: Occupancy,
+    pub position: Position,
+}
+
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct Spawned {
+    pub what: Occupancy,
+    pub position: Position, // Of the head, for segmented creatures
+}
+
+/// Something died, to whatever ate it or, with no killer, to hunger or rot.
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct Died {
+    pub what: Occupancy,
+    pub by: Option<Occupancy>,
+}
+
+/// Something left the arena alive.
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct Escaped {
+    pub what: Occupancy,
+}
+
+/// A move was refused because the way was occupied.
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct Blocked {
+    pub what: Occupancy,
+    pub by: Occupancy,
+    pub position: Position, // Where it tried to go
+}
+
+/// Something lost a segment, to a bite or, with no attacker, to hunger.
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct Damaged {
+    pub what: Occupancy,
+    pub by: Option<Occupancy>,
+}
+
+#[derive(Event, Clone, Debug, Serialize)]
+pub struct TargetChosen {
+    pub what: Occupancy,
+    pub target: Option<Target>,
+}
+
+pub fn update_scoreboard(
+    mut ate: EventReader<Ate>,
+    mut died: EventReader<Died>,
+    mut escaped: EventReader<Escaped>,
+    mut scoreboard: ResMut<Scoreboard>,
+) {
+    for event in ate.read() {
+        match (event.eater, event.food) {
+            (Occupancy::Mongoose(_), Occupancy::Berry(_)) => {
+                scoreboard.berries_eaten_by_mongoose += 1
+            }
+            (Occupancy::Mongoose(_), Occupancy::Rat(_)) => scoreboard.rats_eaten_by_mongoose += 1,
+            (Occupancy::Rat(_), Occupancy::Berry(_)) => scoreboard.berries_eaten_by_rats += 1,
+            (Occupancy::Snake(_), Occupancy::Berry(_)) => scoreboard.berries_eaten_by_snakes += 1,
+            (Occupancy::Snake(_), Occupancy::Rat(_)) => scoreboard.rats_eaten_by_snakes += 1,
+            (eater, food) => {
+                warn!(target: "scores", "Nothing to score for {:?} eating {:?}", eater, food)
+            }
+        }
+    }
+    for event in died.read() {
+        // Snakes that starve don't count, only the ones the mongoose kills
+        if let (Occupancy::Snake(_), Some(Occupancy::Mongoose(_))) = (event.what, event.by) {
+            scoreboard.snakes_killed += 1;
+        }
+    }
+    for event in escaped.read() {
+        if let Occupancy::Rat(_) = event.what {
+            scoreboard.rats_escaped += 1;
+        }
+    }
+}
+
+/// A line of the event log.
+#[derive(Serialize)]
+struct Record<'a> {
+    time: f32, // Seconds into the game
+    #[serde(flatten)]
+    event: LoggedEvent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event")]
+enum LoggedEvent<'a> {
+    Ate(&'a Ate),
+    Spawned(&'a Spawned),
+    Died(&'a Died),
+    Escaped(&'a Escaped),
+    Blocked(&'a Blocked),
+    Damaged(&'a Damaged),
+    TargetChosen(&'a TargetChosen),
+}
+
+/// The current game's event log, if the settings ask for one.
+#[derive(Resource, Default)]
+pub struct EventLog {
+    writer: Option<BufWriter<File>>,
+}
+impl EventLog {
+    /// Starts a new log in the settings' `event_log_dir`, named after the current time.
+    pub fn open(settings: &Settings) -> EventLog {
+        let Some(dir) = &settings.event_log_dir else {
+            return EventLog::default();
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = format!("{}/game-{}.jsonl", dir, timestamp);
+        let file = fs::create_dir_all(dir).and_then(|()| File::create(&path));
+        match file {
+            Ok(file) => {
+                info!(target: "events", "Logging gameplay events to {}", path);
+                EventLog {
+                    writer: Some(BufWriter::new(file)),
+                }
+            }
+            Err(e) => {
+                warn!(target: "events", "Couldn't create event log {}: {}", path, e);
+                EventLog::default()
+            }
+        }
+    }
+    fn write(&mut self, time: f32, event: LoggedEvent) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let result = serde_json::to_writer(&mut *writer, &Record { time, event })
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"));
+        if let Err(e) = result {
+            warn!(target: "events", "Couldn't write to the event log, closing it: {}", e);
+            self.writer = None;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn log_gameplay_events(
+    mut ate: EventReader<Ate>,
+    mut spawned: EventReader<Spawned>,
+    mut died: EventReader<Died>,
+    mut escaped: EventReader<Escaped>,
+    mut blocked: EventReader<Blocked>,
+    mut damaged: EventReader<Damaged>,
+    mut targets_chosen: EventReader<TargetChosen>,
+    clock: Res<GameClock>,
+    mut log: ResMut<EventLog>,
+) {
+    let time = clock.0.elapsed_secs();
+    for event in spawned.read() {
+        log.write(time, LoggedEvent::Spawned(event));
+    }
+    for event in targets_chosen.read() {
+        log.write(time, LoggedEvent::TargetChosen(event));
+    }
+    for event in blocked.read() {
+        log.write(time, LoggedEvent::Blocked(event));
+    }
+    for event in ate.read() {
+        log.write(time, LoggedEvent::Ate(event));
+    }
+    for event in damaged.read() {
+        log.write(time, LoggedEvent::Damaged(event));
+    }
+    for event in escaped.read() {
+        log.write(time, LoggedEvent::Escaped(event));
+    }
+    for event in died.read() {
+        log.write(time, LoggedEvent::Died(event));
+    }
+    if let Some(writer) = &mut log.writer {
+        if let Err(e) = writer.flush() {
+            warn!(target: "events", "Couldn't flush the event log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! High scores, kept per mode in a local RON file, and the table that shows them.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, Modes},
+    hud::{format_time, SCORE_COLOR, TEXT_COLOR},
+    input::{Action, ActionIntent},
+    DeathEvent, GameClock, GameRng, GameState, Mongoose, ReturnState, Scoreboard, Segmented,
+};
+
+pub const HIGH_SCORES_PATH: &str = "highscores.ron";
+const HIGH_SCORES_VERSION: u32 = 1; // Bump whenever HighScore changes shape
+const LEADERBOARD_LENGTH: usize = 10; // Entries kept per mode
+
+const TITLE_FONT_SIZE: f32 = 40.0;
+const ENTRY_FONT_SIZE: f32 = 20.0;
+const TABLE_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HighScore {
+    pub mode: String, // Name of the config the game was played with
+    pub score: f32,
+    pub breakdown: Scoreboard,
+    pub mongoose_length: usize,
+    pub seed: u64,
+    pub duration: f32,
+    pub timestamp: u64, // Seconds since the Unix epoch
+}
+
+#[derive(Deserialize, Serialize)]
+struct HighScoreFile {
+    version: u32,
+    entries: Vec<HighScore>,
+}
+
+// Read first, so an outdated file can be told apart from a corrupt one
+#[derive(Deserialize)]
+struct HighScoreFileVersion {
+    version: u32,
+}
+
+#[derive(Resource)]
+pub struct HighScores {
+    path: String,
+    entries: Vec<HighScore>, // Best first
+}
+impl HighScores {
+    /// Reads the high scores at `path`. A corrupt or outdated file is renamed out of the way, so
+    /// that it isn't lost when the new table is saved over it.
+    pub fn load(path: &str) -> HighScores {
+        let mut high_scores = HighScores {
+            path: path.into(),
+            entries: Vec::new(),
+        };
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return high_scores,
+            Err(e) => {
+                warn!(target: "scores", "Couldn't read high scores {}: {}", path, e);
+                return high_scores;
+            }
+        };
+        match ron::from_str::<HighScoreFileVersion>(&contents) {
+            Ok(HighScoreFileVersion {
+                version: HIGH_SCORES_VERSION,
+            }) => match ron::from_str::<HighScoreFile>(&contents) {
+                Ok(file) => high_scores.entries = file.entries,
+                Err(e) => {
+                    warn!(target: "scores", "High scores {} are corrupt: {}", path, e);
+                    set_aside(path, "corrupt");
+                }
+            },
+            Ok(HighScoreFileVersion { version }) => {
+                warn!(
+                    target: "scores",
+                    "High scores {} are version {}, but version {} is expected",
+                    path, version, HIGH_SCORES_VERSION
+                );
+                set_aside(path, &format!("v{}", version));
+            }
+            Err(e) => {
+                warn!(target: "scores", "High scores {} are corrupt: {}", path, e);
+                set_aside(path, "corrupt");
+            }
+        }
+        high_scores
+    }
+    fn save(&self) {
+        let file = HighScoreFile {
+            version: HIGH_SCORES_VERSION,
+            entries: self.entries.clone(),
+        };
+        match ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(e) = fs::write(&self.path, contents) {
+                    warn!(target: "scores", "Couldn't write high scores {}: {}", self.path, e);
+                }
+            }
+            Err(e) => warn!(target: "scores", "Couldn't serialize high scores: {}", e),
+        }
+    }
+    /// Adds a finished game and saves the table. Returns the game's rank on its mode's
+    /// leaderboard, or `None` if it didn't make the cut.
+    pub fn record(&mut self, high_score: HighScore) -> Option<usize> {
+        let rank = self
+            .leaderboard(&high_score.mode)
+            .filter(|entry| entry.score >= high_score.score)
+            .count();
+        if rank >= LEADERBOARD_LENGTH {
+            return None;
+        }
+        let i = self
+            .entries
+            .iter()
+            .position(|entry| entry.score < high_score.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(i, high_score);
+        // Only the best of each mode are kept
+        let mut counts = HashMap::<String, usize>::new();
+        self.entries.retain(|entry| {
+            let count = counts.entry(entry.mode.clone()).or_default();
+            *count += 1;
+            *count <= LEADERBOARD_LENGTH
+        });
+        self.save();
+        Some(rank)
+    }
+    pub fn leaderboard<'a>(&'a self, mode: &'a str) -> impl Iterator<Item = &'a HighScore> {
+        self.entries.iter().filter(move |entry| entry.mode == mode)
+    }
+    pub fn modes(&self) -> Vec<String> {
+        let mut modes = self
+            .entries
+            .iter()
+            .map(|entry| entry.mode.clone())
+            .collect::<Vec<_>>();
+        modes.sort();
+        modes.dedup();
+        modes
+    }
+}
+
+fn set_aside(path: &str, suffix: &str) {
+    let aside = format!("{}.{}", path, suffix);
+    match fs::rename(path, &aside) {
+        Ok(()) => info!(target: "scores", "Moved {} to {}, starting a new table", path, aside),
+        Err(e) => warn!(target: "scores", "Couldn't move {} to {}: {}", path, aside, e),
+    }
+}
+
+/// The outcome of the most recently finished game.
+#[derive(Resource, Default)]
+pub struct LastGame {
+    pub mode: String,
+    pub score: f32,
+    pub rank: Option<usize>,
+}
+
+#[derive(Component)]
+pub struct HighScoreTable {
+    mode: String,
+}
+
+fn spawn_table(
+    commands: &mut Commands,
+    high_scores: &HighScores,
+    mode: &str,
+    highlight: Option<usize>,
+) {
+    let mut sections = vec![TextSection::new(
+        format!("High scores: {}\n", mode),
+        TextStyle {
+            font_size: TITLE_FONT_SIZE,
+            color: TEXT_COLOR,
+            ..default()
+        },
+    )];
+    for (rank, entry) in high_scores.leaderboard(mode).enumerate() {
+        sections.push(TextSection::new(
+            format!(
+                "{:>2}. {:>5.0}   berries {}, rats {}, length {}   {}   seed {}\n",
+                rank + 1,
+                entry.score,
+                entry.breakdown.berries_eaten_by_mongoose,
+                entry.breakdown.rats_eaten_by_mongoose,
+                entry.mongoose_length,
+                format_time(entry.duration),
+                entry.seed,
+            ),
+            TextStyle {
+                font_size: ENTRY_FONT_SIZE,
+                color: if highlight == Some(rank) {
+                    SCORE_COLOR
+                } else {
+                    TEXT_COLOR
+                },
+                ..default()
+            },
+        ));
+    }
+    if sections.len() == 1 {
+        sections.push(TextSection::new(
+            "No games finished yet\n",
+            TextStyle {
+                font_size: ENTRY_FONT_SIZE,
+                color: TEXT_COLOR,
+                ..default()
+            },
+        ));
+    }
+    sections.push(TextSection::new(
+        "\nLeft/Right for other modes, Enter to go back",
+        TextStyle {
+            font_size: ENTRY_FONT_SIZE,
+            color: TEXT_COLOR,
+            ..default()
+        },
+    ));
+    commands
+        .spawn((
+            HighScoreTable { mode: mode.into() },
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: TABLE_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_sections(sections));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_high_score(
+    mut reader: EventReader<DeathEvent>,
+    mongoose: Query<&Segmented, With<Mongoose>>,
+    config: Res<Config>,
+    scoreboard: Res<Scoreboard>,
+    clock: Res<GameClock>,
+    game_rng: Res<GameRng>,
+    mut high_scores: ResMut<HighScores>,
+    mut last_game: ResMut<LastGame>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in reader.read() {
+        let Ok(segmented) = mongoose.get(event.entity) else {
+            continue;
+        };
+        let mongoose_length = segmented.segments.len();
+        let duration = clock.0.elapsed_secs();
+        let high_score = HighScore {
+            mode: config.name.clone(),
+            score: config.score.score(&scoreboard, mongoose_length, duration),
+            breakdown: scoreboard.clone(),
+            mongoose_length,
+            seed: game_rng.seed,
+            duration,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        };
+        info!(target: "combat", ?high_score, "Game over");
+        *last_game = LastGame {
+            mode: high_score.mode.clone(),
+            score: high_score.score,
+            rank: high_scores.record(high_score),
+        };
+        next_state.set(GameState::GameOver);
+        // It can be killed more than once in a tick, but it only gets one entry
+        break;
+    }
+}
+
+pub fn spawn_high_scores(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    last_game: Res<LastGame>,
+    modes: Res<Modes>,
+) {
+    let mode = &modes.selected().name;
+    let highlight = last_game.rank.filter(|_| last_game.mode == *mode);
+    spawn_table(&mut commands, &high_scores, mode, highlight);
+}
+
+pub fn despawn_high_scores(mut commands: Commands, tables: Query<Entity, With<HighScoreTable>>) {
+    for table in &tables {
+        commands.entity(table).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn navigate_high_scores(
+    mut commands: Commands,
+    mut actions: EventReader<ActionIntent>,
+    tables: Query<(Entity, &HighScoreTable)>,
+    high_scores: Res<HighScores>,
+    last_game: Res<LastGame>,
+    modes: Res<Modes>,
+    return_state: Res<ReturnState>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let actions = actions
+        .read()
+        .map(|intent| intent.action)
+        .collect::<Vec<_>>();
+    if *state.get() != GameState::HighScores {
+        return;
+    }
+    if actions.contains(&Action::Confirm) || actions.contains(&Action::Back) {
+        next_state.set(return_state.0);
+        return;
+    }
+    let Ok((table, shown)) = tables.get_single() else {
+        return;
+    };
+    let step = if actions.contains(&Action::Right) {
+        1
+    } else if actions.contains(&Action::Left) {
+        -1
+    } else {
+        return;
+    };
+    // Modes that have been played, and ones that could be
+    let mut names = high_scores.modes();
+    names.extend(modes.names().map(String::from));
+    names.sort();
+    names.dedup();
+    let i = names
+        .iter()
+        .position(|mode| *mode == shown.mode)
+        .unwrap_or(0) as isize;
+    let mode = &names[(i + step).rem_euclid(names.len() as isize) as usize];
+    let highlight = last_game.rank.filter(|_| last_game.mode == *mode);
+    commands.entity(table).despawn_recursive();
+    spawn_table(&mut commands, &high_scores, mode, highlight);
+}
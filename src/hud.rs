@@ -0,0 +1,135 @@
+//! The heads-up display, a panel with one row per `HudItem` listed in the config.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config, metabolism::Metabolism, Berry, GameClock, Mongoose, Rat, Scoreboard, Segmented,
+    Snake,
+};
+
+const HUD_PADDING: Val = Val::Px(5.0);
+
+pub const TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
+pub const SCORE_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+#[derive(Component)]
+pub struct Hud;
+
+#[derive(Component, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum HudItem {
+    Score,
+    Length,
+    Hunger,
+    Time,
+    BerriesEatenByMongoose,
+    BerriesEatenByRats,
+    BerriesEatenBySnakes,
+    RatsEatenByMongoose,
+    RatsEatenBySnakes,
+    RatsEscaped,
+    SnakesKilled,
+    Berries,
+    Rats,
+    Snakes,
+}
+impl HudItem {
+    fn label(&self) -> &'static str {
+        match self {
+            HudItem::Score => "Score: ",
+            HudItem::Length => "Length: ",
+            HudItem::Hunger => "Hunger: ",
+            HudItem::Time => "Time: ",
+            HudItem::BerriesEatenByMongoose => "Berries eaten: ",
+            HudItem::BerriesEatenByRats => "Berries eaten by rats: ",
+            HudItem::BerriesEatenBySnakes => "Berries eaten by snakes: ",
+            HudItem::RatsEatenByMongoose => "Rats killed: ",
+            HudItem::RatsEatenBySnakes => "Rats eaten by snakes: ",
+            HudItem::RatsEscaped => "Rats escaped: ",
+            HudItem::SnakesKilled => "Snakes killed: ",
+            HudItem::Berries => "Berries: ",
+            HudItem::Rats => "Rats: ",
+            HudItem::Snakes => "Snakes: ",
+        }
+    }
+}
+
+pub fn spawn_hud(mut commands: Commands, config: Res<Config>) {
+    commands
+        .spawn((
+            Hud,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: HUD_PADDING,
+                    left: HUD_PADDING,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for item in config.hud.items.iter() {
+                parent.spawn((
+                    *item,
+                    TextBundle::from_sections([
+                        TextSection::new(
+                            item.label(),
+                            TextStyle {
+                                font_size: config.hud.font_size,
+                                color: TEXT_COLOR,
+                                ..default()
+                            },
+                        ),
+                        TextSection::from_style(TextStyle {
+                            font_size: config.hud.font_size,
+                            color: SCORE_COLOR,
+                            ..default()
+                        }),
+                    ]),
+                ));
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_hud(
+    config: Res<Config>,
+    scoreboard: Res<Scoreboard>,
+    clock: Res<GameClock>,
+    mongoose: Query<(&Segmented, &Metabolism), With<Mongoose>>,
+    berries: Query<(), With<Berry>>,
+    rats: Query<(), With<Rat>>,
+    snakes: Query<(), (With<Snake>, With<Segmented>)>,
+    mut rows: Query<(&HudItem, &mut Text)>,
+) {
+    let (length, hunger) = mongoose
+        .get_single()
+        .map_or((0, 0.0), |(segmented, metabolism)| {
+            (segmented.segments.len(), metabolism.hunger)
+        });
+    let seconds = clock.0.elapsed_secs();
+    for (item, mut text) in &mut rows {
+        text.sections[1].value = match item {
+            HudItem::Score => format!("{:.0}", config.score.score(&scoreboard, length, seconds)),
+            HudItem::Length => length.to_string(),
+            HudItem::Hunger => format!("{:.0}%", hunger * 100.0),
+            HudItem::Time => format_time(seconds),
+            HudItem::BerriesEatenByMongoose => scoreboard.berries_eaten_by_mongoose.to_string(),
+            HudItem::BerriesEatenByRats => scoreboard.berries_eaten_by_rats.to_string(),
+            HudItem::BerriesEatenBySnakes => scoreboard.berries_eaten_by_snakes.to_string(),
+            HudItem::RatsEatenByMongoose => scoreboard.rats_eaten_by_mongoose.to_string(),
+            HudItem::RatsEatenBySnakes => scoreboard.rats_eaten_by_snakes.to_string(),
+            HudItem::RatsEscaped => scoreboard.rats_escaped.to_string(),
+            HudItem::SnakesKilled => scoreboard.snakes_killed.to_string(),
+            HudItem::Berries => berries.iter().count().to_string(),
+            HudItem::Rats => rats.iter().count().to_string(),
+            HudItem::Snakes => snakes.iter().count().to_string(),
+        };
+    }
+}
+
+pub fn format_time(seconds: f32) -> String {
+    format!("{}:{:02}", seconds as u32 / 60, seconds as u32 % 60)
+}
@@ -0,0 +1,436 @@
+//! The input layer. Keyboards and gamepads are turned into `MoveIntent` events here, so that the
+//! movement systems don't need to know where a move came from.
+//!
+//! Fresh presses are queued in the `MoveBuffer` between movement ticks and played back in order, so
+//! quick taps aren't lost. While nothing is queued, the most recently pressed of the held
+//! directions keeps the mongoose moving.
+//!
+//! Gamepads can come and go at any time. Each one is given the lowest free player slot when it
+//! connects; the keyboard always plays for player 0. Buttons that aren't moves, like pausing, are
+//! sent as `ActionIntent` events, which the menus understand as well.
+
+use std::collections::VecDeque;
+
+use bevy::{
+    input::{
+        gamepad::{
+            GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadSettings,
+        },
+        keyboard::KeyboardInput,
+        ButtonState,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{settings::Settings, DOWN, LEFT, RIGHT, UP};
+
+pub const KEYBOARD_PLAYER: usize = 0;
+const MOVE_BUFFER_LENGTH: usize = 3; // Presses remembered between movement ticks
+
+/// A request from `player` to move one tile in `direction`, one of `LEFT`, `UP`, `RIGHT` or `DOWN`.
+/// `held` is set for a direction that was already pressed before this frame.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MoveIntent {
+    pub player: usize,
+    pub direction: usize,
+    pub held: bool,
+}
+
+/// Moves waiting for the next movement tick.
+#[derive(Resource, Default)]
+pub struct MoveBuffer {
+    queue: VecDeque<usize>, // Fresh presses, oldest first
+    held: Option<usize>,    // Latest pressed direction still held down
+}
+impl MoveBuffer {
+    /// Takes in the intents sent since the last tick. Held directions count until intents next
+    /// come in, whereas presses wait in the queue until they are used.
+    pub fn extend(&mut self, intents: impl Iterator<Item = MoveIntent>) {
+        // Only player 0 has a mongoose to move
+        let mut intents = intents
+            .filter(|intent| intent.player == KEYBOARD_PLAYER)
+            .peekable();
+        if intents.peek().is_none() {
+            // No frame since the last tick, so whatever was held still is
+            return;
+        }
+        self.held = None;
+        for intent in intents {
+            if intent.held {
+                self.held = Some(intent.direction);
+            } else if self.queue.back() != Some(&intent.direction)
+                && self.queue.len() < MOVE_BUFFER_LENGTH
+            {
+                self.queue.push_back(intent.direction);
+            }
+        }
+    }
+    /// The direction to move in now, if any.
+    pub fn next(&mut self) -> Option<usize> {
+        self.queue.pop_front().or(self.held)
+    }
+}
+
+/// What moves in each direction, for one kind of device.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DirectionBindings<T> {
+    pub left: Vec<T>,
+    pub up: Vec<T>,
+    pub right: Vec<T>,
+    pub down: Vec<T>,
+}
+impl<T: PartialEq> DirectionBindings<T> {
+    pub fn get(&self, direction: usize) -> &Vec<T> {
+        match direction {
+            LEFT => &self.left,
+            UP => &self.up,
+            RIGHT => &self.right,
+            DOWN => &self.down,
+            _ => panic!("Not a direction: {}", direction),
+        }
+    }
+    fn get_mut(&mut self, direction: usize) -> &mut Vec<T> {
+        match direction {
+            LEFT => &mut self.left,
+            UP => &mut self.up,
+            RIGHT => &mut self.right,
+            DOWN => &mut self.down,
+            _ => panic!("Not a direction: {}", direction),
+        }
+    }
+    /// Makes `input` the only binding for `direction`, taking it away from any other direction.
+    pub fn rebind(&mut self, direction: usize, input: T) {
+        for d in DIRECTIONS {
+            self.get_mut(d).retain(|bound| *bound != input);
+        }
+        *self.get_mut(direction) = vec![input];
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub keys: DirectionBindings<KeyCode>,
+    pub buttons: DirectionBindings<GamepadButtonType>,
+    pub follow: Vec<KeyCode>, // Toggles the camera following the mongoose
+    pub stick: bool,          // Whether the left stick moves as well as the d-pad
+    pub stick_deadzone: f32,  // How far the left stick has to lean to count as a move
+}
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            keys: DirectionBindings {
+                left: vec![KeyCode::ArrowLeft, KeyCode::KeyA],
+                up: vec![KeyCode::ArrowUp, KeyCode::KeyW],
+                right: vec![KeyCode::ArrowRight, KeyCode::KeyD],
+                down: vec![KeyCode::ArrowDown, KeyCode::KeyS],
+            },
+            buttons: DirectionBindings {
+                left: vec![GamepadButtonType::DPadLeft],
+                up: vec![GamepadButtonType::DPadUp],
+                right: vec![GamepadButtonType::DPadRight],
+                down: vec![GamepadButtonType::DPadDown],
+            },
+            follow: vec![KeyCode::KeyF],
+            stick: true,
+            stick_deadzone: 0.5,
+        }
+    }
+}
+impl Bindings {
+    /// Makes `key` the only key for `direction`, taking it away from anything else it did.
+    pub fn rebind_key(&mut self, direction: usize, key: KeyCode) {
+        self.follow.retain(|bound| *bound != key);
+        self.keys.rebind(direction, key);
+    }
+}
+
+pub const DIRECTIONS: [usize; 4] = [LEFT, UP, RIGHT, DOWN];
+
+pub fn direction_name(direction: usize) -> &'static str {
+    match direction {
+        LEFT => "Left",
+        UP => "Up",
+        RIGHT => "Right",
+        DOWN => "Down",
+        _ => panic!("Not a direction: {}", direction),
+    }
+}
+
+/// Buttons that aren't moves. In play, `Pause` and `Back` both pause the game, and `Follow` toggles
+/// the camera following the mongoose.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Pause,
+    Follow,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ActionIntent {
+    #[allow(dead_code)] // Menus and pausing are shared by all players
+    pub player: usize,
+    pub action: Action,
+}
+
+const ACTION_KEYS: [(KeyCode, Action); 8] = [
+    (KeyCode::ArrowUp, Action::Up),
+    (KeyCode::ArrowDown, Action::Down),
+    (KeyCode::ArrowLeft, Action::Left),
+    (KeyCode::ArrowRight, Action::Right),
+    (KeyCode::Enter, Action::Confirm),
+    (KeyCode::Escape, Action::Back),
+    (KeyCode::Backspace, Action::Back),
+    (KeyCode::KeyP, Action::Pause),
+];
+const ACTION_BUTTONS: [(GamepadButtonType, Action); 8] = [
+    (GamepadButtonType::DPadUp, Action::Up),
+    (GamepadButtonType::DPadDown, Action::Down),
+    (GamepadButtonType::DPadLeft, Action::Left),
+    (GamepadButtonType::DPadRight, Action::Right),
+    (GamepadButtonType::South, Action::Confirm),
+    (GamepadButtonType::East, Action::Back),
+    (GamepadButtonType::Start, Action::Pause),
+    (GamepadButtonType::Select, Action::Back),
+];
+
+/// Which player each connected gamepad plays for, indexed by player. A slot is `None` once its
+/// pad has been unplugged, until another pad takes it.
+#[derive(Resource, Default)]
+pub struct GamepadPlayers {
+    pads: Vec<Option<Gamepad>>,
+}
+impl GamepadPlayers {
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Gamepad)> + '_ {
+        self.pads
+            .iter()
+            .enumerate()
+            .filter_map(|(player, pad)| pad.map(|pad| (player, pad)))
+    }
+}
+
+pub fn assign_gamepads(
+    mut connections: EventReader<GamepadConnectionEvent>,
+    mut players: ResMut<GamepadPlayers>,
+) {
+    for event in connections.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                let player = match players.pads.iter().position(Option::is_none) {
+                    Some(player) => {
+                        players.pads[player] = Some(event.gamepad);
+                        player
+                    }
+                    None => {
+                        players.pads.push(Some(event.gamepad));
+                        players.pads.len() - 1
+                    }
+                };
+                info!(
+                    target: "input",
+                    "Gamepad {:?} ({}) connected as player {}",
+                    event.gamepad, info.name, player
+                );
+            }
+            GamepadConnection::Disconnected => {
+                for (player, pad) in players.pads.iter_mut().enumerate() {
+                    if *pad == Some(event.gamepad) {
+                        *pad = None;
+                        info!(
+                            target: "input",
+                            "Gamepad {:?} of player {} disconnected",
+                            event.gamepad, player
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sends a `MoveIntent` for every direction pressed this frame, on any device, in the order they
+/// were pressed, and a held one for each player's most recently pressed direction that is still
+/// down.
+#[allow(clippy::too_many_arguments)]
+pub fn send_move_intents(
+    mut pressed: Local<Vec<(usize, usize)>>, // Players' directions held down, in the order pressed
+    settings: Res<Settings>,
+    mut key_events: EventReader<KeyboardInput>,
+    mut button_events: EventReader<GamepadButtonChangedEvent>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    players: Res<GamepadPlayers>,
+    gamepad_settings: Res<GamepadSettings>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut intents: EventWriter<MoveIntent>,
+) {
+    let bindings = &settings.bindings;
+    // Presses as they came in, which catches taps that start and end within one frame too
+    let mut fresh = Vec::new();
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        if let Some(direction) = DIRECTIONS
+            .into_iter()
+            .find(|direction| bindings.keys.get(*direction).contains(&event.key_code))
+        {
+            fresh.push((KEYBOARD_PLAYER, direction));
+        }
+    }
+    for event in button_events.read() {
+        let button = GamepadButton::new(event.gamepad, event.button_type);
+        if !gamepad_settings
+            .get_button_settings(button)
+            .is_pressed(event.value)
+        {
+            continue;
+        }
+        let Some((player, _)) = players.iter().find(|(_, pad)| *pad == event.gamepad) else {
+            continue;
+        };
+        if let Some(direction) = DIRECTIONS.into_iter().find(|direction| {
+            bindings
+                .buttons
+                .get(*direction)
+                .contains(&event.button_type)
+        }) {
+            fresh.push((player, direction));
+        }
+    }
+    let player_count = players.pads.len().max(KEYBOARD_PLAYER + 1);
+    for player in 0..player_count {
+        let pad = players.pads.get(player).copied().flatten();
+        let down = DIRECTIONS
+            .into_iter()
+            .filter(|direction| {
+                let key = player == KEYBOARD_PLAYER
+                    && keyboard_input.any_pressed(bindings.keys.get(*direction).iter().copied());
+                let button = pad.is_some_and(|pad| {
+                    bindings.buttons.get(*direction).iter().any(|button_type| {
+                        gamepad_buttons.pressed(GamepadButton::new(pad, *button_type))
+                    })
+                });
+                let stick = bindings.stick
+                    && pad.is_some_and(|pad| {
+                        let x = gamepad_axes
+                            .get(GamepadAxis::new(pad, GamepadAxisType::LeftStickX))
+                            .unwrap_or(0.0);
+                        let y = gamepad_axes
+                            .get(GamepadAxis::new(pad, GamepadAxisType::LeftStickY))
+                            .unwrap_or(0.0);
+                        // Only the axis leaning furthest counts, so diagonals pick a side
+                        let deadzone = bindings.stick_deadzone;
+                        match *direction {
+                            LEFT => x < -deadzone && x.abs() >= y.abs(),
+                            UP => y > deadzone && y.abs() > x.abs(),
+                            RIGHT => x > deadzone && x.abs() >= y.abs(),
+                            DOWN => y < -deadzone && y.abs() > x.abs(),
+                            _ => false,
+                        }
+                    });
+                key || button || stick
+            })
+            .collect::<Vec<_>>();
+        pressed.retain(|(p, direction)| *p != player || down.contains(direction));
+        // Presses that came in as events first, in their order, then any only the stick shows
+        let presses = fresh
+            .iter()
+            .filter(|(p, _)| *p == player)
+            .map(|(_, direction)| *direction)
+            .chain(down.iter().copied());
+        for direction in presses {
+            if pressed.contains(&(player, direction)) {
+                continue;
+            }
+            pressed.push((player, direction));
+            intents.send(MoveIntent {
+                player,
+                direction,
+                held: false,
+            });
+        }
+        // A tap that's already been let go is pressed, but not held
+        if let Some((_, direction)) = pressed
+            .iter()
+            .rev()
+            .find(|(p, direction)| *p == player && down.contains(direction))
+        {
+            intents.send(MoveIntent {
+                player,
+                direction: *direction,
+                held: true,
+            });
+        }
+    }
+}
+
+/// Sends an `ActionIntent` for every action button pressed this frame, on any device.
+pub fn send_action_intents(
+    settings: Res<Settings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    players: Res<GamepadPlayers>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut intents: EventWriter<ActionIntent>,
+) {
+    for (key, action) in ACTION_KEYS {
+        if keyboard_input.just_pressed(key) {
+            intents.send(ActionIntent {
+                player: KEYBOARD_PLAYER,
+                action,
+            });
+        }
+    }
+    if keyboard_input.any_just_pressed(settings.bindings.follow.iter().copied()) {
+        intents.send(ActionIntent {
+            player: KEYBOARD_PLAYER,
+            action: Action::Follow,
+        });
+    }
+    for (player, pad) in players.iter() {
+        for (button_type, action) in ACTION_BUTTONS {
+            if gamepad_buttons.just_pressed(GamepadButton::new(pad, button_type)) {
+                intents.send(ActionIntent { player, action });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(direction: usize, held: bool) -> MoveIntent {
+        MoveIntent {
+            player: KEYBOARD_PLAYER,
+            direction,
+            held,
+        }
+    }
+
+    #[test]
+    fn presses_play_back_in_order() {
+        let mut buffer = MoveBuffer::default();
+        buffer.extend([intent(DOWN, false), intent(LEFT, false)].into_iter());
+        assert_eq!(buffer.next(), Some(DOWN));
+        assert_eq!(buffer.next(), Some(LEFT));
+    }
+
+    #[test]
+    fn held_direction_lasts_through_ticks_without_intents() {
+        let mut buffer = MoveBuffer::default();
+        buffer.extend([intent(UP, true)].into_iter());
+        assert_eq!(buffer.next(), Some(UP));
+        buffer.extend(std::iter::empty());
+        assert_eq!(buffer.next(), Some(UP));
+        buffer.extend([intent(RIGHT, false)].into_iter());
+        assert_eq!(buffer.next(), Some(RIGHT));
+        assert_eq!(buffer.next(), None);
+    }
+}
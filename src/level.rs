@@ -0,0 +1,94 @@
+//! The level's scenery, laid out by the config. Walls stand in the way like any other occupant and
+//! can't be seen past. Tall grass can be walked through but not seen past either, and hides
+//! whatever is in it from anything that isn't right next to it.
+
+use array2d::Array2D;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config, sprites::ground_sprite, visibility::line, Arena, Occupancy, Position,
+    ARENA_HEIGHT, ARENA_WIDTH,
+};
+
+const WALL_COLOR: Color = Color::DARK_GRAY;
+const GRASS_COLOR: Color = Color::rgba(0.2, 0.5, 0.1, 0.8);
+
+// Depths under the creatures, which are at 0
+const WALL_Z: f32 = -0.3;
+const GRASS_Z: f32 = -0.6;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LevelConfig {
+    pub walls: Vec<(Position, Position)>, // Straight lines of wall, from one end to the other
+    pub grass: Vec<(Position, Position)>, // Rectangles of tall grass, from one corner to the other
+}
+impl Default for LevelConfig {
+    fn default() -> Self {
+        let p = |x, y| Position { x, y };
+        LevelConfig {
+            walls: vec![(p(7, 2), p(12, 2)), (p(7, 17), p(12, 17))],
+            grass: vec![(p(1, 8), p(3, 11)), (p(16, 8), p(18, 11))],
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Wall;
+
+#[derive(Component)]
+pub struct TallGrass;
+
+/// Which cells have tall grass in them.
+#[derive(Resource)]
+pub struct Terrain {
+    grass: Array2D<bool>,
+}
+impl Default for Terrain {
+    fn default() -> Self {
+        Terrain {
+            grass: Array2D::filled_with(false, ARENA_WIDTH as usize, ARENA_HEIGHT as usize),
+        }
+    }
+}
+impl Terrain {
+    pub fn grass(&self, p: &Position) -> bool {
+        !p.outside_arena() && self.grass[(p.x as usize, p.y as usize)]
+    }
+}
+
+/// Builds the level's walls and lays its grass.
+pub fn build_level(mut commands: Commands, mut arena: ResMut<Arena>, config: Res<Config>) {
+    let mut terrain = Terrain::default();
+    for (from, to) in config.level.grass.iter() {
+        for x in from.x.min(to.x)..=from.x.max(to.x) {
+            for y in from.y.min(to.y)..=from.y.max(to.y) {
+                let position = Position { x, y };
+                if position.outside_arena() || terrain.grass(&position) {
+                    continue;
+                }
+                terrain.grass[(x as usize, y as usize)] = true;
+                commands.spawn((
+                    ground_sprite(GRASS_COLOR, 1.0, GRASS_Z),
+                    TallGrass,
+                    position,
+                ));
+            }
+        }
+    }
+    for (from, to) in config.level.walls.iter() {
+        for position in line(from, to) {
+            if position.outside_arena() || arena.isset(position.x, position.y) {
+                warn!(target: "spawn", ?position, "No room for a wall");
+                continue;
+            }
+            let wall = commands
+                .spawn((ground_sprite(WALL_COLOR, 1.0, WALL_Z), Wall, position))
+                .id();
+            arena.set(position.x, position.y, Occupancy::Wall(wall));
+        }
+    }
+    debug!(target: "spawn", walls = ?config.level.walls, grass = ?config.level.grass, "Built the level");
+    commands.insert_resource(terrain);
+}
@@ -0,0 +1,46 @@
+//! Logging. Everything is logged through `tracing`, with these targets for the game's subsystems:
+//!
+//! - `ai`: planning, targets and paths
+//! - `spawn`: creatures appearing and growing
+//! - `arena`: occupancy and deaths
+//! - `combat`: eating, biting and the end of the game
+//! - `render`: sprites, animations and assets
+//! - `config`: loading and saving game modes and settings
+//! - `input`: gamepads coming and going
+//! - `scores`: the scoreboard and high scores
+//! - `events`: the gameplay event log
+//!
+//! `RUST_LOG` picks what gets logged, e.g. `RUST_LOG=info,ai=trace`. Setting `MONGOOSE_LOG_JSON` to
+//! a path also writes everything that passes the filter there, one JSON object per line.
+
+use std::{env, fs::File, sync::Mutex};
+
+use bevy::log::warn;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+const DEFAULT_FILTER: &str = "info,wgpu=error,naga=warn";
+const JSON_LOG_VAR: &str = "MONGOOSE_LOG_JSON";
+
+/// Installs the global subscriber. This replaces Bevy's `LogPlugin`, which has to be disabled.
+pub fn init_logging() {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let mut json_error = None;
+    let json = env::var(JSON_LOG_VAR)
+        .ok()
+        .and_then(|path| match File::create(&path) {
+            Ok(file) => Some(fmt::layer().json().with_writer(Mutex::new(file))),
+            Err(e) => {
+                json_error = Some(format!("Couldn't create JSON log {}: {}", path, e));
+                None
+            }
+        });
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(json)
+        .init();
+    if let Some(e) = json_error {
+        warn!(target: "config", "{}", e);
+    }
+}
@@ -1,30 +1,90 @@
 use bimap::BiMap;
 use std::cmp::{max, min};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
 use array2d::Array2D;
 use itertools::Itertools;
-use rand::{seq::IteratorRandom, thread_rng, Rng};
-
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+mod animation;
+mod behavior;
+mod berries;
+mod camera;
+mod config;
+mod debug;
+mod fog;
+mod gameplay;
+mod highscores;
+mod hud;
+mod input;
+mod level;
+mod logging;
+mod menu;
+mod metabolism;
+mod reproduction;
+mod segmented;
+mod settings;
+mod sprites;
+mod tween;
+mod utility;
+mod visibility;
+
+use animation::{
+    animate_sprites, clear_corpses, play_animations, spawn_corpse, Animation, AnimationState,
+    PlayAnimation,
+};
+use behavior::{BehaviorState, Behaviors, Mind, BEHAVIORS_DIR};
+use berries::{grow_berries, place_bushes, ripen_berries, tint_berries, Ripeness, Ripening};
 use bevy::{
+    log::LogPlugin,
     prelude::*,
+    time::Stopwatch,
     utils::petgraph::{
         algo::all_simple_paths, graph::NodeIndex, visit::EdgeRef, Graph, Undirected,
     },
     window::WindowResolution,
 };
+use camera::{control_camera, spawn_camera, toggle_follow, LETTERBOX_COLOR};
+use config::{Config, Modes, CONFIG_DIR};
+use debug::{
+    draw_debug_overlay, inspect_cell, spawn_inspector, toggle_debug_overlay, DebugOverlay,
+};
+use fog::{spawn_fog, update_fog, Fog};
+use gameplay::{
+    log_gameplay_events, update_scoreboard, Ate, Blocked, Damaged, Died, Escaped, EventLog,
+    Spawned, TargetChosen,
+};
+use highscores::{
+    despawn_high_scores, navigate_high_scores, record_high_score, spawn_high_scores, HighScores,
+    LastGame, HIGH_SCORES_PATH,
+};
+use hud::{spawn_hud, update_hud, Hud};
+use input::{
+    assign_gamepads, send_action_intents, send_move_intents, Action, ActionIntent, GamepadPlayers,
+    MoveBuffer, MoveIntent,
+};
+use level::{build_level, Terrain};
+use logging::init_logging;
+use menu::{
+    despawn_menus, navigate_menus, spawn_game_over_menu, spawn_main_menu, spawn_pause_menu,
+    spawn_settings_menu, update_menus,
+};
+use metabolism::{digest, feed, Metabolism};
+use reproduction::{breed_rats, hatch_eggs, lay_eggs, Fertility};
+use segmented::Segmented;
+use settings::{apply_settings, Settings, SETTINGS_PATH};
+use sprites::{load_sprite_sheets, SpriteSheets};
+use tween::{start_tweens, Tween};
+use utility::{Utilities, UTILITY_DIR};
+use visibility::{Facing, Sight};
 
 const ARENA_HEIGHT: i32 = 20;
 const ARENA_WIDTH: i32 = 20;
 
 const TILE_SIZE: Vec2 = Vec2::splat(40.0);
 
-const SCOREBOARD_FONT_SIZE: f32 = 40.0;
-const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
-
 const BACKGROUND_COLOR: Color = Color::rgb(0.6, 0.9, 0.2);
-const TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
-const SCORE_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
 
 const SPRITE_SHEET_COLUMNS: usize = 12;
 const SPRITE_SHEET_ROWS: usize = 3;
@@ -47,6 +107,7 @@ const CCW_RIGHT: usize = 10;
 const CCW_DOWN: usize = 11;
 
 const INPUT_PERIOD: f32 = 0.2;
+const MONGOOSE_BODY: [(i32, i32); 3] = [(0, 0), (1, 0), (1, -1)]; // From the middle, head first
 
 const DEBUG_SPEEDUP: f32 = 1.0;
 
@@ -60,20 +121,49 @@ const RAT_PLANNING_PERIOD: f32 = 5.0 / DEBUG_SPEEDUP;
 const SNAKE_MOVEMENT_PERIOD: f32 = 0.3 / DEBUG_SPEEDUP; // How often snakes move
 const SNAKE_PLANNING_PERIOD: f32 = 3.0 / DEBUG_SPEEDUP; // How often snakes replan their goal position
 
-const RAT_BERRY_PREFERENCE: u32 = 4; // Likelihood a rat will choose to chase a berry
-const RAT_WANDER_PREFERENCE: u32 = 3; // Likelihood a rat will choose to go to a random empty location
-
-const SNAKE_RAT_PREFERENCE: u32 = 5; // Likelihood a snake will choose to chase a rat
-const SNAKE_BERRY_PREFERENCE: u32 = 2; // Likelihood a snake will choose to chase a berry
-const SNAKE_WANDER_PREFERENCE: u32 = 2; // Likelihood a snake will choose to go to a random empty location
-
 const MAX_PATH_LENGTH: usize = 8; // Necessary to keep this modest, otherwise all_simple_paths takes forever
+const FLEE_DISTANCE: i32 = (MAX_PATH_LENGTH as i32) / 2; // How far a creature looks for somewhere safer
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum GameState {
+    #[default]
+    MainMenu,
+    Settings,
+    HighScores,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+#[derive(Resource, Default)]
+struct ReturnState(GameState); // Where to go back to from the high scores
 
-#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
 struct Position {
     x: i32,
     y: i32,
 }
+impl Position {
+    /// The centre of this tile in world coordinates, which put the centre of the arena at the
+    /// origin.
+    fn translation(&self) -> Vec3 {
+        Vec3::new(
+            (self.x as f32 - (ARENA_WIDTH - 1) as f32 / 2.0) * TILE_SIZE.x,
+            (self.y as f32 - (ARENA_HEIGHT - 1) as f32 / 2.0) * TILE_SIZE.y,
+            0.0,
+        )
+    }
+    fn outside_arena(&self) -> bool {
+        self.x < 0 || self.x >= ARENA_WIDTH || self.y < 0 || self.y >= ARENA_HEIGHT
+    }
+    /// The tile whose centre is nearest to `translation` in world coordinates.
+    fn from_translation(translation: Vec2) -> Position {
+        Position {
+            x: (translation.x / TILE_SIZE.x + (ARENA_WIDTH - 1) as f32 / 2.0).round() as i32,
+            y: (translation.y / TILE_SIZE.y + (ARENA_HEIGHT - 1) as f32 / 2.0).round() as i32,
+        }
+    }
+}
 
 #[derive(Component)]
 struct Berry;
@@ -87,31 +177,41 @@ struct Rat;
 #[derive(Component)]
 struct Snake;
 
-#[derive(Component)]
-struct Segmented {
-    head_position: Position,
-    segments: Vec<Entity>,
-}
-
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 enum Target {
     Position(Position),
     Entity(Entity),
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Clone, Debug, Default, Deserialize, Serialize)]
 struct Scoreboard {
     berries_eaten_by_mongoose: usize,
     berries_eaten_by_rats: usize,
     berries_eaten_by_snakes: usize,
     rats_eaten_by_mongoose: usize,
     rats_eaten_by_snakes: usize,
-    _rats_escaped: usize,
+    rats_escaped: usize,
     snakes_killed: usize,
 }
 
-#[derive(Component)]
-struct ScoreboardUI;
+#[derive(Resource, Default)]
+struct GameClock(Stopwatch); // Time the mongoose has survived
+
+#[derive(Resource)]
+struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+impl GameRng {
+    fn new(seed: Option<u64>) -> GameRng {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        info!(target: "spawn", seed, "Seeded the game");
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
 
 #[derive(Resource)]
 struct InputTimer(Timer);
@@ -130,12 +230,38 @@ struct GrowEvent {
     segmented: Entity,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Event)]
+struct ShrinkEvent {
+    segmented: Entity,
+    by: Option<Occupancy>, // None for hunger
+}
+
+#[derive(Event)]
+struct DeathEvent {
+    entity: Entity,
+    by: Option<Occupancy>, // None for hunger and rot
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 enum Occupancy {
     Berry(Entity),
     Mongoose(Entity),
     Rat(Entity),
     Snake(Entity),
+    Bush(Entity),
+    Wall(Entity),
+}
+impl Occupancy {
+    fn entity(&self) -> Entity {
+        match *self {
+            Occupancy::Berry(entity)
+            | Occupancy::Mongoose(entity)
+            | Occupancy::Rat(entity)
+            | Occupancy::Snake(entity)
+            | Occupancy::Bush(entity)
+            | Occupancy::Wall(entity) => entity,
+        }
+    }
 }
 
 #[derive(Resource)]
@@ -176,20 +302,21 @@ impl Arena {
         Arena { graph, nodes, occ }
     }
     fn add_edges_with(&mut self, x: i32, y: i32) {
+        // Neighbours can be in the ring just outside the arena, which is how things get in and out
         let n = *self.nodes.get_by_left(&(x, y)).unwrap();
-        if x < (ARENA_WIDTH - 1) && !self.isset(x + 1, y) {
+        if x < ARENA_WIDTH && !self.isset(x + 1, y) {
             self.graph
                 .add_edge(n, *self.nodes.get_by_left(&(x + 1, y)).unwrap(), ());
         }
-        if y < (ARENA_HEIGHT - 1) && !self.isset(x, y + 1) {
+        if y < ARENA_HEIGHT && !self.isset(x, y + 1) {
             self.graph
                 .add_edge(n, *self.nodes.get_by_left(&(x, y + 1)).unwrap(), ());
         }
-        if x > 0 && !self.isset(x - 1, y) {
+        if x > -1 && !self.isset(x - 1, y) {
             self.graph
                 .add_edge(n, *self.nodes.get_by_left(&(x - 1, y)).unwrap(), ());
         }
-        if y > 0 && !self.isset(x, y - 1) {
+        if y > -1 && !self.isset(x, y - 1) {
             self.graph
                 .add_edge(n, *self.nodes.get_by_left(&(x, y - 1)).unwrap(), ());
         }
@@ -240,6 +367,17 @@ impl Arena {
         self.add_edges_with(x, y);
         return occ;
     }
+    fn connected(&self, a: (i32, i32), b: (i32, i32)) -> bool {
+        match (self.nodes.get_by_left(&a), self.nodes.get_by_left(&b)) {
+            (Some(a), Some(b)) => self.graph.contains_edge(*a, *b),
+            _ => false,
+        }
+    }
+    fn edge_count(&self, x: i32, y: i32) -> usize {
+        self.nodes
+            .get_by_left(&(x, y))
+            .map_or(0, |n| self.graph.edges(*n).count())
+    }
     fn isset(&self, x: i32, y: i32) -> bool {
         self.occ(x, y).is_some()
     }
@@ -258,10 +396,11 @@ struct AI {
     plan_timer: Timer,
     path: VecDeque<Position>,
     target: Option<Target>,
+    behavior: BehaviorState,
 }
 impl AI {
     fn plan_path(&mut self, p: &Position, goal: &Position, arena: &mut Arena) {
-        println!("Planning to go from {:?} to {:?}", p, goal);
+        trace!(target: "ai", from = ?p, to = ?goal, "Planning a path");
         // If the things occupy spaces, temporarily unset the positions for pathplanning
         let start_occ = arena.unset(p.x, p.y);
         let goal_occ = arena.unset_maybe(goal.x, goal.y);
@@ -282,7 +421,22 @@ impl AI {
             arena.set(goal.x, goal.y, goal_occ.unwrap());
         }
 
-        if let Some(path) = paths.first() {
+        // The shortest path that stays in the arena, bar its ends, so that only something headed out
+        // of the arena leaves it
+        let inside = |n: &NodeIndex| {
+            let (x, y) = *arena.nodes.get_by_right(n).unwrap();
+            !Position { x, y }.outside_arena()
+        };
+        let path = paths
+            .iter()
+            .filter(|path| {
+                path.iter()
+                    .skip(1)
+                    .take(path.len().saturating_sub(2))
+                    .all(inside)
+            })
+            .min_by_key(|path| path.len());
+        if let Some(path) = path {
             self.path = path
                 .iter()
                 .skip(1)
@@ -305,18 +459,21 @@ impl AI {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_berries(
     mut commands: Commands,
     mut arena: ResMut<Arena>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut game_rng: ResMut<GameRng>,
     time: Res<Time>,
     mut timer: ResMut<BerrySpawnTimer>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
 ) {
     if !timer.0.tick(time.delta()).just_finished() {
         return;
     }
-    let mut rng = thread_rng();
+    let rng = &mut game_rng.rng;
     let (x, y) = loop {
         let x = rng.gen_range(0..ARENA_WIDTH);
         let y = rng.gen_range(0..ARENA_HEIGHT);
@@ -324,117 +481,87 @@ fn spawn_berries(
             break (x, y);
         }
     };
-    let texture = asset_server.load("berry.png");
-    let texture_atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-        TILE_SIZE,
-        SPRITE_SHEET_COLUMNS,
-        SPRITE_SHEET_ROWS,
-        None,
-        None,
-    ));
+    // Berries from nowhere turn up ripe
+    let berry = spawn_berry(
+        &mut commands,
+        &mut arena,
+        &sprite_sheets,
+        &mut spawned,
+        Position { x, y },
+    );
+    commands
+        .entity(berry)
+        .insert(Ripening::new(Ripeness::Ripe, &config));
+}
+
+fn spawn_berry(
+    commands: &mut Commands,
+    arena: &mut Arena,
+    sprite_sheets: &SpriteSheets,
+    spawned: &mut EventWriter<Spawned>,
+    position: Position,
+) -> Entity {
     let berry = commands
-        .spawn((
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                ..default()
-            },
-            Berry,
-            Position { x, y },
-        ))
+        .spawn((sprite_sheets.berry.sprite(), Berry, position))
         .id();
-    arena.set(x, y, Occupancy::Berry(berry));
+    arena.set(position.x, position.y, Occupancy::Berry(berry));
+    spawned.send(Spawned {
+        what: Occupancy::Berry(berry),
+        position,
+    });
+    debug!(target: "spawn", ?berry, ?position, "Spawned berry");
+    berry
 }
 
 fn spawn_mongoose(
     mut commands: Commands,
     mut arena: ResMut<Arena>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    sprite_sheets: Res<SpriteSheets>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
 ) {
-    let texture = asset_server.load("mongoose.png");
-    let texture_atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-        TILE_SIZE,
-        SPRITE_SHEET_COLUMNS,
-        SPRITE_SHEET_ROWS,
-        None,
-        None,
-    ));
-    let (x, y) = (ARENA_WIDTH / 2, ARENA_HEIGHT / 2);
-    let head_position = Position { x, y };
-    let mut segments: Vec<Entity> = Vec::new();
-    let segment = commands
-        .spawn((
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                ..default()
-            },
-            Position { x, y },
-            Mongoose,
-        ))
-        .id();
-    arena.set(x, y, Occupancy::Mongoose(segment));
-    segments.push(segment);
-    let segment = commands
-        .spawn((
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                index: BODY + CCW_LEFT,
-            },
-            Position { x: x + 1, y },
-            Mongoose,
-        ))
+    let mongoose = commands
+        .spawn((Mongoose, Metabolism::new(&config.metabolism.mongoose)))
         .id();
-    arena.set(x + 1, y, Occupancy::Mongoose(segment));
-    segments.push(segment);
-    let segment = commands
-        .spawn((
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                index: TAIL + UP,
-            },
-            Position { x: x + 1, y: y - 1 },
-            Mongoose,
-        ))
-        .id();
-    arena.set(x + 1, y - 1, Occupancy::Mongoose(segment));
-    segments.push(segment);
-    commands.spawn((
-        Segmented {
-            head_position,
-            segments,
+    let cells = MONGOOSE_BODY.map(|(x, y)| Position {
+        x: ARENA_WIDTH / 2 + x,
+        y: ARENA_HEIGHT / 2 + y,
+    });
+    let segmented = Segmented::spawn_along(
+        &mut commands,
+        &mut arena,
+        Occupancy::Mongoose(mongoose),
+        &cells,
+        || {
+            (
+                sprite_sheets.mongoose.sprite(),
+                Tween::new(INPUT_PERIOD),
+                Mongoose,
+            )
         },
-        Mongoose,
-    ));
+    );
+    spawned.send(Spawned {
+        what: Occupancy::Mongoose(mongoose),
+        position: segmented.head_position,
+    });
+    commands.entity(mongoose).insert(segmented);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_rats(
     mut commands: Commands,
     mut arena: ResMut<Arena>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut game_rng: ResMut<GameRng>,
     time: Res<Time>,
     mut timer: ResMut<RatSpawnTimer>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
 ) {
     if !timer.0.tick(time.delta()).just_finished() {
         return;
     }
-    let mut rng = thread_rng();
+    let rng = &mut game_rng.rng;
     let (x, y) = loop {
         let x = rng.gen_range(0..ARENA_WIDTH);
         let y = rng.gen_range(0..ARENA_HEIGHT);
@@ -442,14 +569,24 @@ fn spawn_rats(
             break (x, y);
         }
     };
-    let texture = asset_server.load("rat.png");
-    let texture_atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-        TILE_SIZE,
-        SPRITE_SHEET_COLUMNS,
-        SPRITE_SHEET_ROWS,
-        None,
-        None,
-    ));
+    spawn_rat(
+        &mut commands,
+        &mut arena,
+        &sprite_sheets,
+        &config,
+        &mut spawned,
+        Position { x, y },
+    );
+}
+
+fn spawn_rat(
+    commands: &mut Commands,
+    arena: &mut Arena,
+    sprite_sheets: &SpriteSheets,
+    config: &Config,
+    spawned: &mut EventWriter<Spawned>,
+    position: Position,
+) -> Entity {
     let rat = commands
         .spawn((
             AI {
@@ -457,34 +594,40 @@ fn spawn_rats(
                 plan_timer: Timer::from_seconds(RAT_PLANNING_PERIOD, TimerMode::Once),
                 ..default()
             },
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                ..default()
-            },
+            sprite_sheets.rat.sprite(),
+            Tween::new(RAT_MOVEMENT_PERIOD),
+            Metabolism::new(&config.metabolism.rat),
+            Fertility::new(&config.reproduction.rat),
+            Facing::default(),
             Rat,
-            Position { x, y },
+            position,
         ))
         .id();
-    arena.set(x, y, Occupancy::Rat(rat));
+    arena.set(position.x, position.y, Occupancy::Rat(rat));
+    spawned.send(Spawned {
+        what: Occupancy::Rat(rat),
+        position,
+    });
+    debug!(target: "spawn", ?rat, ?position, "Spawned rat");
+    rat
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_snakes(
-    commands: Commands,
-    arena: ResMut<Arena>,
-    asset_server: Res<AssetServer>,
-    texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut commands: Commands,
+    mut arena: ResMut<Arena>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut game_rng: ResMut<GameRng>,
     mut timer: ResMut<SnakeSpawnTimer>,
     time: Res<Time>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
 ) {
     if !timer.0.tick(time.delta()).just_finished() {
         return;
     }
     // TODO: check distribution of extant snakes to balance spawn locations
-    let mut rng = thread_rng();
+    let rng = &mut game_rng.rng;
     let n = rng.gen_range(0..=3); // number of starting body segments
     let (x, y, delta_x, delta_y) = loop {
         let p = rng.gen_range(0..ARENA_HEIGHT - 1);
@@ -501,10 +644,11 @@ fn spawn_snakes(
         }
     };
     spawn_snake(
-        commands,
-        arena,
-        asset_server,
-        texture_atlas_layouts,
+        &mut commands,
+        &mut arena,
+        &sprite_sheets,
+        &config,
+        &mut spawned,
         x,
         y,
         n,
@@ -513,251 +657,296 @@ fn spawn_snakes(
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_snake(
-    mut commands: Commands,
-    mut arena: ResMut<Arena>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    commands: &mut Commands,
+    arena: &mut Arena,
+    sprite_sheets: &SpriteSheets,
+    config: &Config,
+    spawned: &mut EventWriter<Spawned>,
     x: i32,
     y: i32,
     n: i32,
     delta_x: i32,
     delta_y: i32,
-) {
-    let (mut x, mut y) = (x, y);
-    let texture = asset_server.load("snake.png");
-    let texture_atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-        TILE_SIZE,
-        SPRITE_SHEET_COLUMNS,
-        SPRITE_SHEET_ROWS,
-        None,
-        None,
-    ));
-    let head_position = Position { x, y };
-    let mut segments: Vec<Entity> = Vec::new();
-    let segment = commands
-        .spawn((
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                ..default()
-            },
-            Position { x, y },
-            Snake,
-        ))
-        .id();
-    arena.set(x, y, Occupancy::Snake(segment));
-    segments.push(segment);
-    for _ in 1..=n {
-        x += delta_x;
-        y += delta_y;
-        let segment = commands
-            .spawn((
-                SpriteBundle {
-                    texture: texture.clone(),
-                    ..default()
-                },
-                TextureAtlas {
-                    layout: texture_atlas_layout.clone(),
-                    ..default()
-                },
-                Position { x, y },
+) -> Entity {
+    let snake = commands.spawn_empty().id();
+    let segmented = Segmented::spawn(
+        commands,
+        arena,
+        Occupancy::Snake(snake),
+        Position { x, y },
+        n as usize + 2, // Body segments plus a head and a tail
+        (delta_x, delta_y),
+        || {
+            (
+                sprite_sheets.snake.sprite(),
+                Tween::new(SNAKE_MOVEMENT_PERIOD),
                 Snake,
-            ))
-            .id();
-        arena.set(x, y, Occupancy::Snake(segment));
-        segments.push(segment);
-    }
-    x += delta_x;
-    y += delta_y;
-    let segment = commands
-        .spawn((
-            SpriteBundle {
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: texture_atlas_layout.clone(),
-                ..default()
-            },
-            Position { x, y },
-            Snake,
-        ))
-        .id();
-    arena.set(x, y, Occupancy::Snake(segment));
-    segments.push(segment);
-
-    println!("Spawned segments {:?}", segments);
+            )
+        },
+    );
 
-    let snake = commands
-        .spawn((
-            AI {
-                move_timer: Timer::from_seconds(SNAKE_MOVEMENT_PERIOD, TimerMode::Once),
-                plan_timer: Timer::from_seconds(SNAKE_PLANNING_PERIOD, TimerMode::Once),
-                ..default()
-            },
-            Segmented {
-                head_position,
-                segments,
-            },
-            Snake,
-        ))
-        .id();
-    println!("Snake {:?} spawned with segments", snake);
+    spawned.send(Spawned {
+        what: Occupancy::Snake(snake),
+        position: segmented.head_position,
+    });
+    debug!(target: "spawn", ?snake, segments = ?segmented.segments, "Spawned snake");
+    commands.entity(snake).insert((
+        AI {
+            move_timer: Timer::from_seconds(SNAKE_MOVEMENT_PERIOD, TimerMode::Once),
+            plan_timer: Timer::from_seconds(SNAKE_PLANNING_PERIOD, TimerMode::Once),
+            ..default()
+        },
+        segmented,
+        Metabolism::new(&config.metabolism.snake),
+        Fertility::new(&config.reproduction.snake),
+        Snake,
+    ));
+    snake
 }
 
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
 fn plan_rats(
     berries: Query<(Entity, &Position), With<Berry>>,
-    mut rats: Query<(Entity, &mut AI, &Position), With<Rat>>,
+    predators: Query<&Position, Or<(With<Mongoose>, With<Snake>)>>,
+    mut rats: Query<(Entity, &mut AI, &Metabolism, &Position, &Facing), With<Rat>>,
+    utilities: Res<Utilities>,
+    behaviors: Res<Behaviors>,
     mut arena: ResMut<Arena>,
+    terrain: Res<Terrain>,
+    config: Res<Config>,
+    mut game_rng: ResMut<GameRng>,
+    mut targets_chosen: EventWriter<TargetChosen>,
+    clock: Res<GameClock>,
     time: Res<Time>,
 ) {
-    for (rat, mut ai, position) in &mut rats {
-        if !ai.plan_timer.tick(time.delta()).finished() {
+    let weights = &utilities.rat;
+    for (rat, mut ai, metabolism, position, facing) in &mut rats {
+        ai.plan_timer.tick(time.delta());
+        if !ai.move_timer.finished() {
+            // Creatures only think between steps
             continue;
         }
-        ai.plan_timer.reset();
-
-        if ai.path.len() > 0 {
-            // Already moving toward something
-            continue;
+        let _span = debug_span!(target: "ai", "think", rat = ?rat).entered();
+        // Rats only know about what they can see
+        let sight = Sight::new(
+            &arena,
+            &terrain,
+            position,
+            facing.direction(),
+            &config.vision.rat,
+        );
+        let berries = berries
+            .iter()
+            .filter(|(_, p)| sight.spots(&terrain, p))
+            .map(|(e, p)| (e, *p))
+            .collect::<Vec<_>>();
+        let predators = predators
+            .iter()
+            .filter(|p| sight.spots(&terrain, p))
+            .copied()
+            .collect::<Vec<_>>();
+        let target = ai.target.clone();
+        let status = behaviors.rat.run(&mut Mind {
+            ai: &mut ai,
+            metabolism,
+            position,
+            weights,
+            berries: &berries,
+            rats: &[],
+            predators: &predators,
+            nearest_predator: nearest(position, &predators),
+            arena: &mut arena,
+            rng: &mut game_rng.rng,
+            now: clock.0.elapsed_secs(),
+        });
+        trace!(target: "ai", ?status, active = ai.behavior.active, "Ticked its behavior tree");
+        if ai.path.is_empty() {
+            // Nothing to walk, so think again after another step's worth of time
+            ai.move_timer.reset();
         }
-
-        // No path set, but might already have a target
-        if let Some(goal) = match ai.target {
-            Some(Target::Entity(entity)) => {
-                if let Ok((_, &position)) = berries.get(entity) {
-                    Some(position)
-                } else {
-                    // Target despawned
-                    None
-                }
-            }
-            Some(Target::Position(position)) => Some(position),
-            None => None,
-        } {
-            ai.plan_path(&position, &goal, &mut arena);
-            println!("Rat {:?}, target {:?}, path {:?}", rat, ai.target, ai.path);
-            continue;
-        } else {
-            // Target despawned
-            ai.abandon_target();
+        if ai.target != target {
+            targets_chosen.send(TargetChosen {
+                what: Occupancy::Rat(rat),
+                target: ai.target.clone(),
+            });
+            debug!(target: "ai", ?position, target = ?ai.target, "Chose a target");
         }
-
-        // No target, so maybe choose a new one
-        let mut rng = thread_rng();
-        let roll = rng.gen_range(0..10);
-        ai.target = if roll <= RAT_BERRY_PREFERENCE {
-            println!("Rat {:?} looking for a berry target", rat);
-            choose_random_entity(&berries, &position)
-        } else if roll < RAT_WANDER_PREFERENCE + RAT_BERRY_PREFERENCE {
-            // Choose a random location as the target
-            println!("Rat {:?} looking for a random location", rat);
-            choose_random_unocc(&position, &arena)
-        } else {
-            println!("Rat {:?} is twiddling its thumbs", rat);
-            None
-        };
-
-        println!(
-            "Rat {:?}, position={:?}, target={:?}",
-            rat, position, ai.target
-        );
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
 fn plan_snakes(
     berries: Query<(Entity, &Position), With<Berry>>,
-    rats: Query<(Entity, &Position), With<Rat>>,
-    mut snakes: Query<(Entity, &mut AI, &Segmented), With<Snake>>,
+    rats: Query<(Entity, &Position, &AI), (With<Rat>, Without<Snake>)>,
+    predators: Query<&Position, With<Mongoose>>,
+    mut snakes: Query<(Entity, &mut AI, &Metabolism, &Segmented), With<Snake>>,
+    positions: Query<&Position>,
+    terrain: Res<Terrain>,
+    config: Res<Config>,
+    utilities: Res<Utilities>,
+    behaviors: Res<Behaviors>,
     mut arena: ResMut<Arena>,
+    mut game_rng: ResMut<GameRng>,
+    mut targets_chosen: EventWriter<TargetChosen>,
+    clock: Res<GameClock>,
     time: Res<Time>,
 ) {
-    for (snake, mut ai, segmented) in &mut snakes {
-        if !ai.plan_timer.tick(time.delta()).finished() {
+    let weights = &utilities.snake;
+    for (snake, mut ai, metabolism, segmented) in &mut snakes {
+        let position = &segmented.head_position;
+        ai.plan_timer.tick(time.delta());
+        if !ai.move_timer.finished() {
+            // Creatures only think between steps
             continue;
         }
-        ai.plan_timer.reset();
-
-        if ai.path.len() > 0 {
-            // Already moving toward something
-            continue;
+        let _span = debug_span!(target: "ai", "think", snake = ?snake).entered();
+        // Snakes only know about what they can see, and head for where a rat is going to be rather
+        // than where it is
+        let sight = Sight::new(
+            &arena,
+            &terrain,
+            position,
+            segmented.facing(&positions),
+            &config.vision.snake,
+        );
+        let berries = berries
+            .iter()
+            .filter(|(_, p)| sight.spots(&terrain, p))
+            .map(|(e, p)| (e, *p))
+            .collect::<Vec<_>>();
+        let rats = rats
+            .iter()
+            .filter(|(_, p, _)| sight.spots(&terrain, p))
+            .map(|(rat, p, rat_ai)| {
+                let heading = intercept(
+                    position,
+                    SNAKE_MOVEMENT_PERIOD,
+                    p,
+                    &rat_ai.path,
+                    RAT_MOVEMENT_PERIOD,
+                );
+                trace!(target: "ai", ?rat, at = ?p, ?heading, "Sees a rat");
+                (rat, heading)
+            })
+            .collect::<Vec<_>>();
+        let predators = predators
+            .iter()
+            .filter(|p| sight.spots(&terrain, p))
+            .copied()
+            .collect::<Vec<_>>();
+        let target = ai.target.clone();
+        let status = behaviors.snake.run(&mut Mind {
+            ai: &mut ai,
+            metabolism,
+            position,
+            weights,
+            berries: &berries,
+            rats: &rats,
+            predators: &predators,
+            nearest_predator: nearest(position, &predators),
+            arena: &mut arena,
+            rng: &mut game_rng.rng,
+            now: clock.0.elapsed_secs(),
+        });
+        trace!(target: "ai", ?status, active = ai.behavior.active, "Ticked its behavior tree");
+        if ai.path.is_empty() {
+            // Nothing to walk, so think again after another step's worth of time
+            ai.move_timer.reset();
         }
-
-        // No path set, but might already have a target
-        if let Some(goal) = match ai.target {
-            Some(Target::Entity(entity)) => {
-                if let Ok((_, &position)) = berries.get(entity) {
-                    Some(position)
-                } else if let Ok((_, &position)) = rats.get(entity) {
-                    Some(position)
-                } else {
-                    // Target despawned
-                    None
-                }
-            }
-            Some(Target::Position(position)) => Some(position),
-            None => None,
-        } {
-            ai.plan_path(&segmented.head_position, &goal, &mut arena);
-            println!("Snake {:?}, path {:?}", snake, ai.path);
-            continue;
-        } else {
-            // Target despawned
-            ai.abandon_target();
+        if ai.target != target {
+            targets_chosen.send(TargetChosen {
+                what: Occupancy::Snake(snake),
+                target: ai.target.clone(),
+            });
+            debug!(target: "ai", ?position, target = ?ai.target, "Chose a target");
         }
+    }
+}
 
-        let mut rng = thread_rng();
-        let roll = rng.gen_range(0..10);
-        ai.target = if roll <= SNAKE_RAT_PREFERENCE {
-            println!("Snake {:?} looking for a rat target", snake);
-            choose_random_entity(&rats, &segmented.head_position)
-        } else if roll <= SNAKE_BERRY_PREFERENCE + SNAKE_RAT_PREFERENCE {
-            println!("Snake {:?} looking for a berry target", snake);
-            choose_random_entity(&berries, &segmented.head_position)
-        } else if roll < SNAKE_WANDER_PREFERENCE + SNAKE_BERRY_PREFERENCE + SNAKE_RAT_PREFERENCE {
-            // Choose a random location as the target
-            println!("Snake {:?} looking for a random location", snake);
-            choose_random_unocc(&segmented.head_position, &arena)
-        } else {
-            println!("Snake {:?} is twiddling its thumbs", snake);
-            None
-        };
+fn distance(a: &Position, b: &Position) -> i32 {
+    (a.x - b.x).abs() + (a.y - b.y).abs()
+}
 
-        println!(
-            "Snake {:?}, head position={:?}, target={:?}",
-            snake, segmented.head_position, ai.target
-        );
-    }
+fn nearest(position: &Position, others: &[Position]) -> Option<i32> {
+    others.iter().map(|other| distance(position, other)).min()
 }
 
-fn choose_random_entity<T: Component>(
-    query: &Query<(Entity, &Position), With<T>>,
-    position: &Position,
-) -> Option<Target> {
-    // Try to choose a random berry that's not too far away
-    let mut rng = thread_rng();
-    if let Some((entity, _)) = query
+/// The things that are close enough to `position` to plan a path to.
+fn within_reach(position: &Position, things: &[(Entity, Position)]) -> Vec<(Entity, Position)> {
+    things
         .iter()
-        .filter(|(_, p)| (position.x + position.y - p.x - p.y).abs() as usize <= MAX_PATH_LENGTH)
-        .choose(&mut rng)
-    {
-        Some(Target::Entity(entity))
-    } else {
-        None
+        .filter(|(_, p)| distance(position, p) as usize <= MAX_PATH_LENGTH)
+        .copied()
+        .collect()
+}
+
+/// The empty cell within `FLEE_DISTANCE` of `position` that is furthest from every predator. Cells
+/// just outside the arena, where a creature can escape, count as `exit_preference` further away.
+fn choose_flee_position(
+    position: &Position,
+    predators: &[Position],
+    exit_preference: i32,
+    arena: &Arena,
+) -> Option<Position> {
+    if predators.is_empty() {
+        return None;
     }
+    let x_min = max(-1, position.x - FLEE_DISTANCE);
+    let y_min = max(-1, position.y - FLEE_DISTANCE);
+    let x_max = min(ARENA_WIDTH, position.x + FLEE_DISTANCE);
+    let y_max = min(ARENA_HEIGHT, position.y + FLEE_DISTANCE);
+    (x_min..=x_max)
+        .cartesian_product(y_min..=y_max)
+        .map(|(x, y)| Position { x, y })
+        .filter(|p| {
+            p != position
+                && distance(position, p) <= FLEE_DISTANCE
+                && !arena.isset(p.x, p.y)
+                && !predators.contains(p)
+        })
+        .max_by_key(|p| {
+            let safety = nearest(p, predators).unwrap_or(i32::MAX);
+            if p.outside_arena() {
+                safety.saturating_add(exit_preference)
+            } else {
+                safety
+            }
+        })
+}
+
+/// Where a hunter that steps every `hunter_period` seconds should head to catch prey at `prey` that
+/// is walking `path`, a step every `prey_period` seconds. That's the first cell on the path the
+/// hunter can get to no later than the prey, or the end of the path if it can't get to any in time.
+fn intercept(
+    hunter: &Position,
+    hunter_period: f32,
+    prey: &Position,
+    path: &VecDeque<Position>,
+    prey_period: f32,
+) -> Position {
+    // Prey heading out of the arena can only be caught before it leaves
+    let path = path
+        .iter()
+        .take_while(|p| !p.outside_arena())
+        .collect::<Vec<_>>();
+    path.iter()
+        .enumerate()
+        .find(|(i, p)| distance(hunter, p) as f32 * hunter_period <= (i + 1) as f32 * prey_period)
+        .map(|(_, p)| **p)
+        .or_else(|| path.last().copied().copied())
+        .unwrap_or(*prey)
 }
 
-fn choose_random_unocc(position: &Position, arena: &ResMut<Arena>) -> Option<Target> {
+fn choose_random_unocc(position: &Position, arena: &Arena, rng: &mut impl Rng) -> Option<Position> {
     // Limit the distance to reflect MAX_PATH_LENGTH
     let x_min = max(0, position.x - (MAX_PATH_LENGTH as i32) / 2);
     let y_min = max(0, position.y - (MAX_PATH_LENGTH as i32) / 2);
     let x_max = min(ARENA_WIDTH - 1, position.x + (MAX_PATH_LENGTH as i32) / 2);
     let y_max = min(ARENA_HEIGHT - 1, position.y + (MAX_PATH_LENGTH as i32) / 2);
-    let mut rng = thread_rng();
     let mut attempts = 10;
     let (x, y) = loop {
         let (x, y) = (rng.gen_range(x_min..=x_max), rng.gen_range(y_min..=y_max));
@@ -769,61 +958,43 @@ fn choose_random_unocc(position: &Position, arena: &ResMut<Arena>) -> Option<Tar
             return None;
         }
     };
-    Some(Target::Position(Position { x, y }))
+    Some(Position { x, y })
 }
 
+#[allow(clippy::too_many_arguments)] // Bevy systems take their world access as arguments
 fn move_mongoose(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut commands: Commands,
-    mut scoreboard: ResMut<Scoreboard>,
+    mut intents: EventReader<MoveIntent>,
+    mut move_buffer: ResMut<MoveBuffer>,
     mut mongoose: Query<(Entity, &mut Segmented), With<Mongoose>>,
-    positions: Query<&mut Position, With<Mongoose>>,
+    mut positions: Query<&mut Position, With<Mongoose>>,
     mut arena: ResMut<Arena>,
     mut input_timer: ResMut<InputTimer>,
+    mut growths: EventWriter<GrowEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut animations: EventWriter<PlayAnimation>,
+    mut ate: EventWriter<Ate>,
+    mut blocked: EventWriter<Blocked>,
     time: Res<Time>,
 ) {
-    // TODO move this into a keyboard_input system
-    // This system will take events instead
+    move_buffer.extend(intents.read().copied());
     if !input_timer.0.tick(time.delta()).finished() {
         return;
     }
-
-    let mut delta_x = 0;
-    let mut delta_y = 0;
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        delta_x -= 1;
-    }
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
-        delta_x += 1;
-    }
-    if keyboard_input.pressed(KeyCode::ArrowUp) {
-        delta_y += 1;
-    }
-    if keyboard_input.pressed(KeyCode::ArrowDown) {
-        delta_y -= 1;
-    }
-
-    if delta_x != 0 && delta_y != 0 {
-        // No moving diagonally
-        return;
-    }
-    if delta_x == 0 && delta_y == 0 {
+    let Some(next_direction) = move_buffer.next() else {
         return;
-    }
-
-    let next_direction = if delta_x < 0 {
-        LEFT
-    } else if delta_y > 0 {
-        UP
-    } else if delta_x > 0 {
-        RIGHT
-    } else if delta_y < 0 {
-        DOWN
-    } else {
-        panic!();
+    };
+    let (delta_x, delta_y) = match next_direction {
+        LEFT => (-1, 0),
+        UP => (0, 1),
+        RIGHT => (1, 0),
+        DOWN => (0, -1),
+        _ => panic!(),
     };
 
-    let (mongoose, segmented) = mongoose.get_single_mut().expect("Mongoose entity missing");
+    let Ok((mongoose, mut segmented)) = mongoose.get_single_mut() else {
+        // The mongoose has died
+        return;
+    };
 
     if segmented.head_position.x == 0 && next_direction == LEFT {
         return;
@@ -842,64 +1013,101 @@ fn move_mongoose(
         segmented.head_position.y + delta_y,
     );
     match arena.occ(x, y) {
-        None => move_mongoose_segments(arena, mongoose, segmented, positions, delta_x, delta_y),
+        None => segmented.advance(Position { x, y }, &mut arena, &mut positions),
         Some(Occupancy::Berry(berry)) => {
             arena.unset(x, y);
-            move_mongoose_segments(arena, mongoose, segmented, positions, delta_x, delta_y);
-            commands.entity(berry).despawn();
-            scoreboard.berries_eaten_by_mongoose += 1;
-            println!("Berry {:?} eaten by mongoose", berry)
+            segmented.advance(Position { x, y }, &mut arena, &mut positions);
+            deaths.send(DeathEvent {
+                entity: berry,
+                by: Some(Occupancy::Mongoose(mongoose)),
+            });
+            growths.send(GrowEvent {
+                segmented: mongoose,
+            });
+            animations.send(PlayAnimation {
+                entity: segmented.segments[0],
+                state: AnimationState::Eating,
+            });
+            ate.send(Ate {
+                eater: Occupancy::Mongoose(mongoose),
+                food: Occupancy::Berry(berry),
+                position: Position { x, y },
+            });
+            debug!(target: "combat", ?berry, "Mongoose ate a berry")
         }
         Some(Occupancy::Rat(rat)) => {
             arena.unset(x, y);
-            move_mongoose_segments(arena, mongoose, segmented, positions, delta_x, delta_y);
-            commands.entity(rat).despawn();
-            scoreboard.rats_eaten_by_mongoose += 1;
-            println!("Rat {:?} eaten by mongoose", rat)
+            segmented.advance(Position { x, y }, &mut arena, &mut positions);
+            deaths.send(DeathEvent {
+                entity: rat,
+                by: Some(Occupancy::Mongoose(mongoose)),
+            });
+            growths.send(GrowEvent {
+                segmented: mongoose,
+            });
+            animations.send(PlayAnimation {
+                entity: segmented.segments[0],
+                state: AnimationState::Eating,
+            });
+            ate.send(Ate {
+                eater: Occupancy::Mongoose(mongoose),
+                food: Occupancy::Rat(rat),
+                position: Position { x, y },
+            });
+            debug!(target: "combat", ?rat, "Mongoose ate a rat")
+        }
+        // TODO: Mongoose attacking Snakes is unimplemented
+        Some(
+            occupancy @ (Occupancy::Snake(_)
+            | Occupancy::Mongoose(_)
+            | Occupancy::Bush(_)
+            | Occupancy::Wall(_)),
+        ) => {
+            blocked.send(Blocked {
+                what: Occupancy::Mongoose(mongoose),
+                by: occupancy,
+                position: Position { x, y },
+            });
         }
-        Some(Occupancy::Snake(_snake)) => (), // TODO: Mongoose attacking Snakes is unimplemented
-        Some(Occupancy::Mongoose(_)) => (),
     }
     input_timer.0.reset();
 }
 
-fn move_mongoose_segments(
-    mut arena: ResMut<Arena>,
-    mongoose: Entity,
-    mut segmented: Mut<Segmented>,
-    mut positions: Query<&mut Position, With<Mongoose>>,
-    delta_x: i32,
-    delta_y: i32,
-) {
-    segmented.head_position.x += delta_x;
-    segmented.head_position.y += delta_y;
-    arena.set(
-        segmented.head_position.x,
-        segmented.head_position.y,
-        Occupancy::Mongoose(mongoose),
-    );
-    let mut gap_position = segmented.head_position.clone();
-    for s in segmented.segments.iter() {
-        let mut position = positions.get_mut(*s).unwrap();
-        (position.x, gap_position.x) = (gap_position.x, position.x);
-        (position.y, gap_position.y) = (gap_position.y, position.y);
-    }
-    arena.unset(gap_position.x, gap_position.y);
-}
-
+#[allow(clippy::too_many_arguments)]
 fn move_rats(
-    mut commands: Commands,
-    mut scoreboard: ResMut<Scoreboard>,
-    mut rats: Query<(Entity, &mut AI, &mut Position), With<Rat>>,
+    mut rats: Query<(Entity, &mut AI, &mut Position, &mut Facing), With<Rat>>,
     mut arena: ResMut<Arena>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut animations: EventWriter<PlayAnimation>,
+    mut ate: EventWriter<Ate>,
+    mut blocked: EventWriter<Blocked>,
+    mut escaped: EventWriter<Escaped>,
     time: Res<Time>,
 ) {
-    for (rat, mut ai, mut position) in &mut rats {
+    for (rat, mut ai, mut position, mut facing) in &mut rats {
         if !ai.move_timer.tick(time.delta()).finished() {
             continue;
         }
         if let Some(next_position) = ai.path.pop_front() {
+            // Whether or not it gets there, it turns to go
+            *facing = Facing::towards(&position, &next_position);
             match arena.occ(next_position.x, next_position.y) {
+                // Only a rat fleeing to an exit leaves the arena
+                None if next_position.outside_arena()
+                    && ai.target == Some(Target::Position(next_position)) =>
+                {
+                    arena.unset(position.x, position.y);
+                    *position = next_position;
+                    ai.abandon_target();
+                    escaped.send(Escaped {
+                        what: Occupancy::Rat(rat),
+                    });
+                    debug!(target: "combat", ?rat, ?position, "Rat escaped");
+                }
+                None if next_position.outside_arena() => {
+                    trace!(target: "ai", ?rat, edge = ?next_position, "Rat turned back at the edge");
+                    ai.abandon_path();
+                }
                 None => {
                     arena.unset(position.x, position.y);
                     (position.x, position.y) = (next_position.x, next_position.y);
@@ -910,15 +1118,28 @@ fn move_rats(
                     arena.unset(next_position.x, next_position.y);
                     (position.x, position.y) = (next_position.x, next_position.y);
                     arena.set(position.x, position.y, Occupancy::Rat(rat));
-                    commands.entity(berry).despawn();
-                    scoreboard.berries_eaten_by_rats += 1;
-                    println!("Berry {:?} eaten by rat", berry)
+                    deaths.send(DeathEvent {
+                        entity: berry,
+                        by: Some(Occupancy::Rat(rat)),
+                    });
+                    animations.send(PlayAnimation {
+                        entity: rat,
+                        state: AnimationState::Eating,
+                    });
+                    ate.send(Ate {
+                        eater: Occupancy::Rat(rat),
+                        food: Occupancy::Berry(berry),
+                        position: next_position,
+                    });
+                    debug!(target: "combat", ?rat, ?berry, "Rat ate a berry")
                 }
-                Some(_) => {
-                    println!(
-                        "Rat {:?}, position ({}, {}) is blocked",
-                        rat, next_position.x, next_position.y
-                    );
+                Some(occupancy) => {
+                    blocked.send(Blocked {
+                        what: Occupancy::Rat(rat),
+                        by: occupancy,
+                        position: next_position,
+                    });
+                    trace!(target: "ai", ?rat, blocked = ?next_position, "Rat is blocked");
                     ai.abandon_path();
                 }
             }
@@ -927,66 +1148,113 @@ fn move_rats(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn move_snakes(
-    mut commands: Commands,
-    mut scoreboard: ResMut<Scoreboard>,
     mut snakes: Query<(Entity, &mut AI, &mut Segmented), With<Snake>>,
     mut positions: Query<&mut Position, With<Snake>>,
+    mongoose: Query<Entity, (With<Mongoose>, With<Segmented>)>,
     mut arena: ResMut<Arena>,
     mut writer: EventWriter<GrowEvent>,
+    mut shrinks: EventWriter<ShrinkEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut animations: EventWriter<PlayAnimation>,
+    mut ate: EventWriter<Ate>,
+    mut blocked: EventWriter<Blocked>,
+    mut damaged: EventWriter<Damaged>,
     time: Res<Time>,
 ) {
-    for (snake, mut ai, segmented) in &mut snakes {
+    for (snake, mut ai, mut segmented) in &mut snakes {
         if !ai.move_timer.tick(time.delta()).finished() {
             continue;
         }
         if let Some(next_position) = ai.path.pop_front() {
             let (x, y) = (next_position.x, next_position.y);
             match arena.occ(x, y) {
-                None => {
-                    move_snake_segments(&mut arena, snake, segmented, &mut positions, next_position)
-                }
+                None => segmented.advance(next_position, &mut arena, &mut positions),
                 Some(Occupancy::Berry(berry)) => {
                     arena.unset(x, y);
-                    move_snake_segments(
-                        &mut arena,
-                        snake,
-                        segmented,
-                        &mut positions,
-                        next_position,
-                    );
-                    commands.entity(berry).despawn();
-                    scoreboard.berries_eaten_by_snakes += 1;
+                    segmented.advance(next_position, &mut arena, &mut positions);
+                    deaths.send(DeathEvent {
+                        entity: berry,
+                        by: Some(Occupancy::Snake(snake)),
+                    });
+                    ate.send(Ate {
+                        eater: Occupancy::Snake(snake),
+                        food: Occupancy::Berry(berry),
+                        position: next_position,
+                    });
                     writer.send(GrowEvent { segmented: snake });
-                    println!("Snake {:?} ate berry {:?}", snake, berry)
+                    animations.send(PlayAnimation {
+                        entity: segmented.segments[0],
+                        state: AnimationState::Eating,
+                    });
+                    debug!(target: "combat", ?snake, ?berry, "Snake ate a berry")
                 }
                 Some(Occupancy::Rat(rat)) => {
                     arena.unset(x, y);
-                    move_snake_segments(
-                        &mut arena,
-                        snake,
-                        segmented,
-                        &mut positions,
-                        next_position,
-                    );
-                    commands.entity(rat).despawn();
-                    scoreboard.rats_eaten_by_snakes += 1;
+                    segmented.advance(next_position, &mut arena, &mut positions);
+                    deaths.send(DeathEvent {
+                        entity: rat,
+                        by: Some(Occupancy::Snake(snake)),
+                    });
+                    ate.send(Ate {
+                        eater: Occupancy::Snake(snake),
+                        food: Occupancy::Rat(rat),
+                        position: next_position,
+                    });
                     writer.send(GrowEvent { segmented: snake });
-                    println!("Snake {:?} ate rat {:?}", snake, rat)
+                    animations.send(PlayAnimation {
+                        entity: segmented.segments[0],
+                        state: AnimationState::Eating,
+                    });
+                    debug!(target: "combat", ?snake, ?rat, "Snake ate a rat")
                 }
-                Some(Occupancy::Mongoose(_)) => {
-                    // Snakes attacking Mongoose is unimplemented
-                    println!(
-                        "Snake {:?}, position ({}, {}) is blocked by mongoose",
-                        snake, next_position.x, next_position.y
-                    );
+                Some(occupancy @ Occupancy::Mongoose(_)) => {
+                    // The snake bites, but the mongoose still blocks the way
+                    if let Ok(mongoose) = mongoose.get_single() {
+                        shrinks.send(ShrinkEvent {
+                            segmented: mongoose,
+                            by: Some(Occupancy::Snake(snake)),
+                        });
+                        damaged.send(Damaged {
+                            what: Occupancy::Mongoose(mongoose),
+                            by: Some(Occupancy::Snake(snake)),
+                        });
+                    }
+                    blocked.send(Blocked {
+                        what: Occupancy::Snake(snake),
+                        by: occupancy,
+                        position: next_position,
+                    });
+                    animations.send(PlayAnimation {
+                        entity: segmented.segments[0],
+                        state: AnimationState::Attacking,
+                    });
+                    debug!(target: "combat", ?snake, at = ?next_position, "Snake bit the mongoose");
+                    ai.abandon_path();
+                }
+                Some(occupancy @ (Occupancy::Bush(_) | Occupancy::Wall(_))) => {
+                    blocked.send(Blocked {
+                        what: Occupancy::Snake(snake),
+                        by: occupancy,
+                        position: next_position,
+                    });
+                    trace!(target: "ai", ?snake, blocked = ?next_position, "Snake is blocked by scenery");
                     ai.abandon_path();
                 }
-                Some(Occupancy::Snake(other_snake)) => {
+                Some(occupancy @ Occupancy::Snake(other_snake)) => {
                     // other_snake is equal to snake if the snake bumps into itself
-                    println!(
-                        "Snake {:?}, position ({}, {}) is blocked by snake {:?}",
-                        snake, next_position.x, next_position.y, other_snake
+                    blocked.send(Blocked {
+                        what: Occupancy::Snake(snake),
+                        by: occupancy,
+                        position: next_position,
+                    });
+                    trace!(
+                        target: "ai",
+                        ?snake,
+                        blocked = ?next_position,
+                        ?other_snake,
+                        "Snake is blocked by a snake"
                     );
                     ai.abandon_path();
                 }
@@ -996,59 +1264,33 @@ fn move_snakes(
     }
 }
 
-fn move_snake_segments(
-    arena: &mut ResMut<Arena>,
-    snake: Entity,
-    mut segmented: Mut<Segmented>,
-    positions: &mut Query<&mut Position, With<Snake>>,
-    next_position: Position,
-) {
-    segmented.head_position.x = next_position.x;
-    segmented.head_position.y = next_position.y;
-    arena.set(
-        segmented.head_position.x,
-        segmented.head_position.y,
-        Occupancy::Snake(snake),
-    );
-    let mut gap_position = segmented.head_position.clone();
-    let mut grown = false;
-    for s in segmented.segments.iter() {
-        let mut position = positions.get_mut(*s).unwrap();
-        (position.x, gap_position.x) = (gap_position.x, position.x);
-        (position.y, gap_position.y) = (gap_position.y, position.y);
-        if position.x == gap_position.x && position.y == gap_position.y {
-            grown = true;
+fn transformation(time: Res<Time>, mut q: Query<(&Position, &mut Transform, Option<&mut Tween>)>) {
+    for (pos, mut transform, tween) in &mut q {
+        // Keep each sprite's own depth, so that things on the ground stay under the creatures
+        let z = transform.translation.z;
+        let Some(mut tween) = tween else {
+            transform.translation = pos.translation().truncate().extend(z);
+            continue;
+        };
+        transform.translation = match tween.tick(time.delta_seconds()) {
+            Some((from, to, t)) => from.translation().lerp(to.translation(), t),
+            None => pos.translation(),
         }
-    }
-    if !grown {
-        arena.unset(gap_position.x, gap_position.y);
-    }
-}
-
-fn transformation(window: Query<&Window>, mut q: Query<(&Position, &mut Transform)>) {
-    fn convert(pos: f32, bound_window: f32, bound_game: f32) -> f32 {
-        let tile_size = bound_window / bound_game;
-        pos / bound_game * bound_window - (bound_window / 2.) + (tile_size / 2.)
-    }
-    let window = window.single();
-    for (pos, mut transform) in &mut q {
-        transform.translation = Vec3::new(
-            convert(pos.x as f32, window.width() as f32, ARENA_WIDTH as f32),
-            convert(pos.y as f32, window.height() as f32, ARENA_HEIGHT as f32),
-            0.0,
-        );
+        .truncate()
+        .extend(z);
+        transform.rotation = tween.rotation();
     }
 }
 
 fn set_segment_sprites(
     things: Query<(Entity, &Segmented, Has<Mongoose>)>,
-    mut query: Query<(&Position, &mut TextureAtlas)>,
+    mut query: Query<(&Position, &mut Animation, Option<&mut Tween>)>,
 ) {
     'things: for (thing, segmented, is_mongoose) in &things {
         // TODO do this only after movement, maybe check for a needs_redraw flag
         let i_tail = segmented.segments.len() - 2;
         for (i, (f, b)) in segmented.segments.iter().tuple_windows().enumerate() {
-            let [(pos_f, mut ta_f), (pos_b, mut ta_b)] = query
+            let [(pos_f, mut anim_f, tween_f), (pos_b, mut anim_b, _)] = query
                 .get_many_mut([*f, *b])
                 .expect("Failed to get segments pair");
 
@@ -1075,17 +1317,25 @@ fn set_segment_sprites(
                 );
             };
             if direction == None {
-                ta_f.index += TAIL;
-                ta_b.index = SPRITE_SHEET_COLUMNS - 1; // Should be a blank sprite
+                anim_f.base += TAIL;
+                anim_b.base = SPRITE_SHEET_COLUMNS - 1; // Should be a blank sprite
                 continue 'things;
             }
             let direction = direction.unwrap();
             if i == 0 {
                 // Entity f is the head segment
-                ta_f.index = HEAD + direction;
+                let previous_direction = anim_f.base - HEAD;
+                if let Some(mut tween) = tween_f {
+                    if direction == (previous_direction + 1) % 4 {
+                        tween.turn(true);
+                    } else if previous_direction == (direction + 1) % 4 {
+                        tween.turn(false);
+                    }
+                }
+                anim_f.base = HEAD + direction;
             } else {
-                ta_f.index = BODY
-                    + match (direction, ta_f.index) {
+                anim_f.base = BODY
+                    + match (direction, anim_f.base) {
                         (LEFT, LEFT) => LEFT,
                         (UP, UP) => UP,
                         (RIGHT, RIGHT) => RIGHT,
@@ -1098,154 +1348,229 @@ fn set_segment_sprites(
                         (RIGHT, UP) => CCW_UP,
                         (DOWN, RIGHT) => CCW_RIGHT,
                         (LEFT, DOWN) => CCW_DOWN,
-                        _ => panic!("Nonsense pair of directions {} {}", direction, ta_f.index),
+                        _ => panic!("Nonsense pair of directions {} {}", direction, anim_f.base),
                     };
             }
             if i == i_tail {
                 // Entity b is the tail segment
-                ta_b.index = TAIL + direction;
+                anim_b.base = TAIL + direction;
             } else {
-                ta_b.index = direction;
+                anim_b.base = direction;
             }
         }
     }
 }
 
-fn grow_snakes(
+fn grow_segmented(
     mut commands: Commands,
-    mut snakes: Query<(Entity, &mut Segmented), With<Snake>>,
+    mut things: Query<(Entity, &mut Segmented, Has<Mongoose>)>,
     positions: Query<&Position>,
     mut reader: EventReader<GrowEvent>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    sprite_sheets: Res<SpriteSheets>,
 ) {
     for event in reader.read() {
-        if let Ok((snake, mut segmented)) = snakes.get_mut(event.segmented) {
-            let texture = asset_server.load("snake.png");
-            let texture_atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-                TILE_SIZE,
-                SPRITE_SHEET_COLUMNS,
-                SPRITE_SHEET_ROWS,
-                None,
-                None,
-            ));
-            let tail_position = positions
-                .get(
-                    *segmented
-                        .segments
-                        .last()
-                        .expect(&format!("Snake {:?}. segments vector is empty", snake)),
+        if let Ok((thing, mut segmented, is_mongoose)) = things.get_mut(event.segmented) {
+            let new_segment = if is_mongoose {
+                segmented.grow(
+                    &mut commands,
+                    &positions,
+                    (
+                        sprite_sheets.mongoose.sprite(),
+                        Tween::new(INPUT_PERIOD),
+                        Mongoose,
+                    ),
                 )
-                .expect(&format!(
-                    "Snake {:?}, length {:?}. tail segment position missing",
-                    snake,
-                    segmented.segments.len(),
-                )) // FIXME: clippy will convert this so the format occured only when an error occurs
-                .clone();
-            let new_segment = commands
-                .spawn((
-                    SpriteBundle {
-                        texture: texture.clone(),
-                        ..default()
-                    },
-                    TextureAtlas {
-                        layout: texture_atlas_layout.clone(),
-                        ..default()
-                    },
-                    tail_position,
-                    Snake,
-                ))
-                .id();
-            println!("Snake {:?} got new segment {:?}", snake, new_segment);
-            segmented.segments.push(new_segment);
+            } else {
+                segmented.grow(
+                    &mut commands,
+                    &positions,
+                    (
+                        sprite_sheets.snake.sprite(),
+                        Tween::new(SNAKE_MOVEMENT_PERIOD),
+                        Snake,
+                    ),
+                )
+            };
+            debug!(target: "spawn", ?thing, segment = ?new_segment, "Grew a segment");
         } else {
-            panic!("Error getting snake {:?}", event.segmented); // FIXME turn this into a log after snakes can despawn
+            // The creature died before it could digest
+            debug!(target: "spawn", thing = ?event.segmented, "Gone, dropping its growth");
         }
     }
 }
 
-fn spawn_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+fn shrink_segmented(
+    mut commands: Commands,
+    mut things: Query<&mut Segmented>,
+    positions: Query<&Position>,
+    mut arena: ResMut<Arena>,
+    mut reader: EventReader<ShrinkEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+) {
+    for event in reader.read() {
+        let Ok(mut segmented) = things.get_mut(event.segmented) else {
+            debug!(target: "combat", thing = ?event.segmented, "Gone, nothing to shrink");
+            continue;
+        };
+        if let Some(tail) = segmented.shrink(&mut commands, &mut arena, &positions) {
+            debug!(target: "combat", thing = ?event.segmented, segment = ?tail, "Lost a segment");
+        } else {
+            debug!(target: "combat", thing = ?event.segmented, "Shrank away to nothing");
+            deaths.send(DeathEvent {
+                entity: event.segmented,
+                by: event.by,
+            });
+        }
+    }
 }
 
-fn spawn_scoreboard(mut commands: Commands) {
-    commands.spawn((
-        ScoreboardUI,
-        TextBundle::from_sections([
-            TextSection::new(
-                "Score: ",
-                TextStyle {
-                    font_size: SCOREBOARD_FONT_SIZE,
-                    color: TEXT_COLOR,
-                    ..default()
-                },
-            ),
-            TextSection::from_style(TextStyle {
-                font_size: SCOREBOARD_FONT_SIZE,
-                color: SCORE_COLOR,
-                ..default()
-            }),
-        ])
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: SCOREBOARD_TEXT_PADDING,
-            left: SCOREBOARD_TEXT_PADDING,
-            ..default()
-        }),
-    ));
+fn tick_clock(
+    mongoose: Query<(), (With<Mongoose>, With<Segmented>)>,
+    mut clock: ResMut<GameClock>,
+    time: Res<Time>,
+) {
+    if !mongoose.is_empty() {
+        clock.0.tick(time.delta());
+    }
+}
+
+/// A spawner that goes off every `period` seconds, or never.
+fn spawn_timer(period: Option<f32>) -> Timer {
+    let mut timer = Timer::from_seconds(period.unwrap_or(0.0), TimerMode::Repeating);
+    if period.is_none() {
+        timer.pause();
+    }
+    timer
 }
 
-fn update_scoreboard(scoreboard: Res<Scoreboard>, mut query: Query<&mut Text, With<ScoreboardUI>>) {
-    let mut text = query.single_mut();
-    text.sections[1].value = (scoreboard.berries_eaten_by_mongoose
-        + scoreboard.rats_eaten_by_mongoose
-        + scoreboard.snakes_killed)
-        .to_string();
+fn setup_game(mut commands: Commands, modes: Res<Modes>, settings: Res<Settings>) {
+    let config = modes.selected().clone();
+    info!(target: "config", mode = config.name, "Starting a game");
+    commands.insert_resource(Arena::new());
+    commands.insert_resource(GameRng::new(config.seed));
+    commands.insert_resource(Scoreboard::default());
+    commands.insert_resource(GameClock::default());
+    commands.insert_resource(EventLog::open(&settings));
+    commands.insert_resource(MoveBuffer::default());
+    commands.insert_resource(InputTimer(Timer::from_seconds(
+        INPUT_PERIOD,
+        TimerMode::Once,
+    )));
+    commands.insert_resource(BerrySpawnTimer(spawn_timer(config.spawning.berry_period)));
+    commands.insert_resource(RatSpawnTimer(spawn_timer(config.spawning.rat_period)));
+    commands.insert_resource(SnakeSpawnTimer(spawn_timer(config.spawning.snake_period)));
+    commands.insert_resource(config);
+}
+
+#[allow(clippy::type_complexity)]
+fn teardown_game(
+    mut commands: Commands,
+    things: Query<Entity, Or<(With<Position>, With<Segmented>, With<Hud>)>>,
+) {
+    for thing in &things {
+        commands.entity(thing).despawn_recursive();
+    }
+}
+
+fn pause_game(
+    mut actions: EventReader<ActionIntent>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pause = actions
+        .read()
+        .any(|intent| matches!(intent.action, Action::Back | Action::Pause));
+    if pause && *state.get() == GameState::Playing {
+        next_state.set(GameState::Paused);
+    }
 }
 
 fn main() {
+    init_logging();
     App::new()
         .add_plugins(
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Mongoose!".into(),
-                    resolution: WindowResolution::new(
-                        TILE_SIZE[0] * ARENA_WIDTH as f32,
-                        TILE_SIZE[1] * ARENA_HEIGHT as f32,
-                    )
-                    .with_scale_factor_override(1.0),
+            DefaultPlugins
+                .build()
+                .disable::<LogPlugin>()
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Mongoose!".into(),
+                        resolution: WindowResolution::new(
+                            TILE_SIZE[0] * ARENA_WIDTH as f32,
+                            TILE_SIZE[1] * ARENA_HEIGHT as f32,
+                        )
+                        .with_scale_factor_override(1.0),
+                        ..default()
+                    }),
                     ..default()
                 }),
-                ..default()
-            }),
         )
         .add_event::<GrowEvent>()
-        .insert_resource(Arena::new())
-        .insert_resource(Scoreboard { ..default() })
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
-        .insert_resource(InputTimer(Timer::from_seconds(
-            INPUT_PERIOD,
-            TimerMode::Once,
-        )))
-        .insert_resource(BerrySpawnTimer(Timer::from_seconds(
-            BERRY_SPAWN_PERIOD,
-            TimerMode::Repeating,
-        )))
-        .insert_resource(RatSpawnTimer(Timer::from_seconds(
-            RAT_SPAWN_PERIOD,
-            TimerMode::Repeating,
-        )))
-        .insert_resource(SnakeSpawnTimer(Timer::from_seconds(
-            SNAKE_SPAWN_PERIOD,
-            TimerMode::Repeating,
-        )))
+        .add_event::<ShrinkEvent>()
+        .add_event::<DeathEvent>()
+        .add_event::<MoveIntent>()
+        .add_event::<PlayAnimation>()
+        .add_event::<ActionIntent>()
+        .add_event::<Ate>()
+        .add_event::<Spawned>()
+        .add_event::<Died>()
+        .add_event::<Escaped>()
+        .add_event::<Blocked>()
+        .add_event::<Damaged>()
+        .add_event::<TargetChosen>()
+        .init_state::<GameState>()
+        .insert_resource(Modes::load(CONFIG_DIR))
+        .insert_resource(HighScores::load(HIGH_SCORES_PATH))
+        .insert_resource(Utilities::load(UTILITY_DIR))
+        .insert_resource(Behaviors::load(BEHAVIORS_DIR))
+        .init_resource::<LastGame>()
+        .init_resource::<ReturnState>()
+        .init_resource::<GamepadPlayers>()
+        .init_resource::<DebugOverlay>()
+        .insert_resource(Settings::load(SETTINGS_PATH))
+        .insert_resource(ClearColor(LETTERBOX_COLOR))
+        .add_systems(Startup, (spawn_camera, load_sprite_sheets, spawn_inspector))
+        .add_systems(
+            OnEnter(GameState::MainMenu),
+            (teardown_game, spawn_main_menu),
+        )
+        .add_systems(OnEnter(GameState::Settings), spawn_settings_menu)
+        .add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+        .add_systems(OnEnter(GameState::GameOver), spawn_game_over_menu)
+        .add_systems(OnEnter(GameState::HighScores), spawn_high_scores)
+        .add_systems(OnExit(GameState::MainMenu), despawn_menus)
+        .add_systems(OnExit(GameState::Settings), despawn_menus)
+        .add_systems(OnExit(GameState::Paused), despawn_menus)
+        .add_systems(OnExit(GameState::GameOver), despawn_menus)
+        .add_systems(OnExit(GameState::HighScores), despawn_high_scores)
         .add_systems(
-            Startup,
+            OnTransition {
+                from: GameState::MainMenu,
+                to: GameState::Playing,
+            },
             (
-                spawn_camera,
-                spawn_scoreboard,
+                setup_game,
+                spawn_hud,
                 spawn_mongoose,
-                //test_spawn_snake,
+                build_level,
+                place_bushes,
+                spawn_fog,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnTransition {
+                from: GameState::GameOver,
+                to: GameState::Playing,
+            },
+            (
+                teardown_game,
+                setup_game,
+                spawn_hud,
+                spawn_mongoose,
+                build_level,
+                place_bushes,
+                spawn_fog,
             )
                 .chain(),
         )
@@ -1259,33 +1584,91 @@ fn main() {
                 plan_snakes,
                 move_snakes,
                 move_mongoose,
-                grow_snakes,
+                feed,
+                digest,
+                shrink_segmented,
+                // Scores count this tick's deaths, and the mongoose is still there to be measured
+                // until the despawns are applied after all three
+                (despawn_dead, update_scoreboard, record_high_score).chain_ignore_deferred(),
+                forget_dead_targets,
+                grow_segmented,
+                start_tweens,
                 set_segment_sprites,
-                spawn_berries,
-                transformation,
-                detect_removals,
+                (
+                    spawn_berries,
+                    grow_berries,
+                    ripen_berries,
+                    tint_berries,
+                    breed_rats,
+                    lay_eggs,
+                    hatch_eggs,
+                )
+                    .chain(),
+                tick_clock,
+                log_gameplay_events,
             )
-                .chain(),
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                // Everything that reads actions runs in every state, so that none are left over
+                // to be read again after a state change
+                (
+                    assign_gamepads,
+                    send_action_intents,
+                    pause_game,
+                    navigate_menus,
+                    update_menus,
+                    navigate_high_scores,
+                    toggle_follow,
+                )
+                    .chain(),
+                (send_move_intents, update_hud).run_if(in_state(GameState::Playing)),
+                // Nothing moves or animates while the game is paused
+                (
+                    transformation,
+                    update_fog.run_if(resource_exists::<Fog>),
+                    control_camera,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+                (play_animations, animate_sprites, clear_corpses)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+                (
+                    toggle_debug_overlay,
+                    draw_debug_overlay.run_if(
+                        resource_exists::<Arena>
+                            .and_then(|overlay: Res<DebugOverlay>| overlay.enabled),
+                    ),
+                    inspect_cell,
+                )
+                    .chain(),
+                apply_settings,
+            ),
         )
-        .add_systems(Update, (update_scoreboard, bevy::window::close_on_esc))
         .run();
 }
 
 #[allow(dead_code)] // FIXME
 fn test_spawn_snake(
-    commands: Commands,
-    arena: ResMut<Arena>,
-    asset_server: Res<AssetServer>,
-    texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut commands: Commands,
+    mut arena: ResMut<Arena>,
+    sprite_sheets: Res<SpriteSheets>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
 ) {
     let (x, y) = (3, 0);
     let n = 1;
     let (delta_x, delta_y) = (-1, 0);
     spawn_snake(
-        commands,
-        arena,
-        asset_server,
-        texture_atlas_layouts,
+        &mut commands,
+        &mut arena,
+        &sprite_sheets,
+        &config,
+        &mut spawned,
         x,
         y,
         n,
@@ -1296,25 +1679,105 @@ fn test_spawn_snake(
 
 #[allow(dead_code)] // FIXME
 fn pretty_print(a: &Array2D<bool>) {
-    println!();
+    let mut grid = String::new();
     for y in 0..ARENA_HEIGHT as usize {
+        grid.push('\n');
         for x in 0..ARENA_WIDTH as usize {
-            print!(
-                "{} ",
-                if a[(x, (ARENA_HEIGHT as usize) - 1 - y)] {
-                    "1"
-                } else {
-                    "0"
-                }
-            );
+            grid.push_str(if a[(x, (ARENA_HEIGHT as usize) - 1 - y)] {
+                "1 "
+            } else {
+                "0 "
+            });
         }
-        println!();
     }
+    debug!(target: "arena", "{}", grid);
 }
 
-fn detect_removals(mut removals: RemovedComponents<Position>) {
-    for entity in removals.read() {
-        // do something with the entity
-        eprintln!("Entity {:?} position removed.", entity);
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+fn despawn_dead(
+    mut commands: Commands,
+    mut reader: EventReader<DeathEvent>,
+    mut escapes: EventReader<Escaped>,
+    segmented: Query<&Segmented>,
+    positions: Query<&Position>,
+    kinds: Query<(Has<Berry>, Has<Mongoose>, Has<Rat>, Has<Snake>)>,
+    sprites: Query<(&Animation, &Position)>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut arena: ResMut<Arena>,
+    mut died: EventWriter<Died>,
+) {
+    // Whatever escaped the arena is despawned too, just without dying
+    let deaths = reader.read().map(|event| (event.entity, true, event.by));
+    let escapes = escapes
+        .read()
+        .map(|event| (event.what.entity(), false, None));
+    let mut gone = HashSet::new();
+    for (entity, died_here, by) in deaths.chain(escapes) {
+        if !gone.insert(entity) {
+            continue;
+        }
+        if died_here {
+            let what = match kinds.get(entity) {
+                Ok((true, _, _, _)) => Some(Occupancy::Berry(entity)),
+                Ok((_, true, _, _)) => Some(Occupancy::Mongoose(entity)),
+                Ok((_, _, true, _)) => Some(Occupancy::Rat(entity)),
+                Ok((_, _, _, true)) => Some(Occupancy::Snake(entity)),
+                _ => None,
+            };
+            if let Some(what) = what {
+                died.send(Died { what, by });
+            }
+        }
+        // A segmented creature is its root entity plus every segment entity
+        let mut entities = vec![entity];
+        if let Ok(segmented) = segmented.get(entity) {
+            segmented.vacate(&mut arena, &positions);
+            entities.extend(segmented.segments.iter());
+        } else if let Ok(position) = positions.get(entity) {
+            // Only clear the cell if it's still held; whoever ate this may already be standing there
+            if arena
+                .occ(position.x, position.y)
+                .is_some_and(|occ| occ.entity() == entity)
+            {
+                arena.unset(position.x, position.y);
+            }
+        }
+        for entity in entities.iter() {
+            if let (true, Ok((animation, position))) = (died_here, sprites.get(*entity)) {
+                spawn_corpse(&mut commands, &sprite_sheets, animation, *position);
+            }
+            if let Some(mut entity_commands) = commands.get_entity(*entity) {
+                entity_commands.despawn();
+            }
+        }
+        if died_here {
+            debug!(target: "arena", ?entity, "Died");
+        } else {
+            debug!(target: "arena", ?entity, "Left the arena");
+        }
+    }
+}
+
+fn forget_dead_targets(
+    mut reader: EventReader<DeathEvent>,
+    mut escapes: EventReader<Escaped>,
+    mut ais: Query<(Entity, &mut AI)>,
+) {
+    let dead = reader
+        .read()
+        .map(|event| event.entity)
+        .chain(escapes.read().map(|event| event.what.entity()))
+        .collect::<HashSet<_>>();
+    if dead.is_empty() {
+        return;
+    }
+    for (entity, mut ai) in &mut ais {
+        if let Some(Target::Entity(target)) = ai.target {
+            if dead.contains(&target) {
+                debug!(target: "ai", ?entity, ?target, "Target is gone, abandoning it");
+                ai.abandon_target();
+            }
+        }
     }
 }
@@ -0,0 +1,312 @@
+//! Menus for the screens around the game itself: the main menu, settings, pause and game over.
+//! Up and Down pick an item, Enter activates it, and Left and Right change the value of items that
+//! have one. In the settings menu, Enter on a direction waits for the key or gamepad button to bind
+//! to it.
+
+use bevy::{app::AppExit, prelude::*};
+use itertools::Itertools;
+
+use crate::{
+    config::Modes,
+    highscores::LastGame,
+    hud::{SCORE_COLOR, TEXT_COLOR},
+    input::{direction_name, Action, ActionIntent, Bindings, DIRECTIONS},
+    settings::Settings,
+    GameState, ReturnState,
+};
+
+const TITLE_FONT_SIZE: f32 = 60.0;
+const ITEM_FONT_SIZE: f32 = 30.0;
+const MENU_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const DEADZONE_STEP: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    Start,
+    Mode,
+    Settings,
+    HighScores,
+    Quit,
+    Resume,
+    Restart,
+    MainMenu,
+    Fullscreen,
+    FollowMongoose,
+    Bind(usize), // A direction
+    StickDeadzone,
+    ResetControls,
+    Back,
+}
+
+#[derive(Component)]
+pub struct Menu {
+    items: Vec<MenuItem>,
+    selected: usize,
+    capturing: Option<usize>, // Direction waiting for a new binding
+}
+
+#[derive(Component)]
+pub struct MenuEntry(usize); // Index into Menu::items
+
+fn spawn_menu(commands: &mut Commands, title: String, items: Vec<MenuItem>) {
+    let n = items.len();
+    commands
+        .spawn((
+            Menu {
+                items,
+                selected: 0,
+                capturing: None,
+            },
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: MENU_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: TITLE_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+            for i in 0..n {
+                // Text is filled in by update_menus
+                parent.spawn((
+                    MenuEntry(i),
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: ITEM_FONT_SIZE,
+                            color: TEXT_COLOR,
+                            ..default()
+                        },
+                    ),
+                ));
+            }
+        });
+}
+
+pub fn spawn_main_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        "Mongoose!".into(),
+        vec![
+            MenuItem::Start,
+            MenuItem::Mode,
+            MenuItem::Settings,
+            MenuItem::HighScores,
+            MenuItem::Quit,
+        ],
+    );
+}
+
+pub fn spawn_settings_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        "Settings".into(),
+        [MenuItem::Fullscreen, MenuItem::FollowMongoose]
+            .into_iter()
+            .chain(DIRECTIONS.map(MenuItem::Bind))
+            .chain([
+                MenuItem::StickDeadzone,
+                MenuItem::ResetControls,
+                MenuItem::Back,
+            ])
+            .collect(),
+    );
+}
+
+pub fn spawn_pause_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        "Paused".into(),
+        vec![MenuItem::Resume, MenuItem::MainMenu, MenuItem::Quit],
+    );
+}
+
+pub fn spawn_game_over_menu(mut commands: Commands, last_game: Res<LastGame>) {
+    spawn_menu(
+        &mut commands,
+        format!("Game over\nScore: {:.0}", last_game.score),
+        vec![MenuItem::Restart, MenuItem::HighScores, MenuItem::MainMenu],
+    );
+}
+
+pub fn despawn_menus(mut commands: Commands, menus: Query<Entity, With<Menu>>) {
+    for menu in &menus {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn navigate_menus(
+    mut actions: EventReader<ActionIntent>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut menus: Query<&mut Menu>,
+    mut modes: ResMut<Modes>,
+    mut settings: ResMut<Settings>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut return_state: ResMut<ReturnState>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let actions = actions
+        .read()
+        .map(|intent| intent.action)
+        .collect::<Vec<_>>();
+    if let Ok(mut menu) = menus.get_single_mut() {
+        if let Some(direction) = menu.capturing {
+            // Raw input, since any key or button can be bound
+            if keyboard_input.just_pressed(KeyCode::Escape) {
+                menu.capturing = None;
+            } else if let Some(key) = keyboard_input.get_just_pressed().next() {
+                settings.bindings.rebind_key(direction, *key);
+                menu.capturing = None;
+            } else if let Some(button) = gamepad_buttons.get_just_pressed().next() {
+                settings
+                    .bindings
+                    .buttons
+                    .rebind(direction, button.button_type);
+                menu.capturing = None;
+            }
+            return;
+        }
+    }
+    let back = actions.contains(&Action::Back);
+    match state.get() {
+        GameState::Settings | GameState::GameOver if back => {
+            next_state.set(GameState::MainMenu);
+            return;
+        }
+        GameState::Paused if back || actions.contains(&Action::Pause) => {
+            next_state.set(GameState::Playing);
+            return;
+        }
+        _ => (),
+    }
+    let Ok(mut menu) = menus.get_single_mut() else {
+        return;
+    };
+    let n = menu.items.len();
+    if actions.contains(&Action::Up) {
+        menu.selected = (menu.selected + n - 1) % n;
+    }
+    if actions.contains(&Action::Down) {
+        menu.selected = (menu.selected + 1) % n;
+    }
+    let item = menu.items[menu.selected];
+    let confirm = actions.contains(&Action::Confirm);
+    let step = if actions.contains(&Action::Left) {
+        -1
+    } else if actions.contains(&Action::Right) || confirm {
+        1
+    } else {
+        return;
+    };
+    match item {
+        MenuItem::Mode => modes.cycle(step),
+        MenuItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+        MenuItem::FollowMongoose => settings.follow_mongoose = !settings.follow_mongoose,
+        MenuItem::StickDeadzone => {
+            let deadzone = settings.bindings.stick_deadzone + step as f32 * DEADZONE_STEP;
+            settings.bindings.stick_deadzone = deadzone.clamp(DEADZONE_STEP, 1.0 - DEADZONE_STEP);
+        }
+        _ if !confirm => (),
+        MenuItem::Start | MenuItem::Restart | MenuItem::Resume => {
+            next_state.set(GameState::Playing)
+        }
+        MenuItem::Settings => next_state.set(GameState::Settings),
+        MenuItem::Bind(direction) => menu.capturing = Some(direction),
+        MenuItem::ResetControls => settings.bindings = Bindings::default(),
+        MenuItem::HighScores => {
+            return_state.0 = *state.get();
+            next_state.set(GameState::HighScores);
+        }
+        MenuItem::MainMenu | MenuItem::Back => next_state.set(GameState::MainMenu),
+        MenuItem::Quit => {
+            exit.send(AppExit);
+        }
+    }
+}
+
+pub fn update_menus(
+    menus: Query<&Menu>,
+    mut entries: Query<(&MenuEntry, &mut Text)>,
+    modes: Res<Modes>,
+    settings: Res<Settings>,
+) {
+    let Ok(menu) = menus.get_single() else {
+        return;
+    };
+    for (entry, mut text) in &mut entries {
+        let section = &mut text.sections[0];
+        section.value = match menu.items[entry.0] {
+            MenuItem::Start => "Start".into(),
+            MenuItem::Mode => format!("< Mode: {} >", modes.selected().name),
+            MenuItem::Settings => "Settings".into(),
+            MenuItem::HighScores => "High scores".into(),
+            MenuItem::Quit => "Quit".into(),
+            MenuItem::Resume => "Resume".into(),
+            MenuItem::Restart => "Restart".into(),
+            MenuItem::MainMenu => "Main menu".into(),
+            MenuItem::Fullscreen => format!(
+                "< Fullscreen: {} >",
+                if settings.fullscreen { "on" } else { "off" }
+            ),
+            MenuItem::Bind(direction) if menu.capturing == Some(direction) => format!(
+                "{}: press a key or button (Esc to cancel)",
+                direction_name(direction)
+            ),
+            MenuItem::Bind(direction) => format!(
+                "{}: {}",
+                direction_name(direction),
+                settings
+                    .bindings
+                    .keys
+                    .get(direction)
+                    .iter()
+                    .map(|key| format!("{:?}", key))
+                    .chain(
+                        settings
+                            .bindings
+                            .buttons
+                            .get(direction)
+                            .iter()
+                            .map(|button| format!("{:?}", button))
+                    )
+                    .join(", ")
+            ),
+            MenuItem::FollowMongoose => format!(
+                "< Follow mongoose: {} >",
+                if settings.follow_mongoose {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+            MenuItem::StickDeadzone => format!(
+                "< Stick deadzone: {:.0}% >",
+                settings.bindings.stick_deadzone * 100.0
+            ),
+            MenuItem::ResetControls => "Reset controls".into(),
+            MenuItem::Back => "Back".into(),
+        };
+        section.style.color = if entry.0 == menu.selected {
+            SCORE_COLOR
+        } else {
+            TEXT_COLOR
+        };
+    }
+}
@@ -0,0 +1,129 @@
+//! Hunger. Every creature gets hungrier over time and less hungry by eating, berries and rats
+//! being worth different amounts. A starving creature loses a segment every so often, and dies
+//! once it has none left to lose, or straight away if it never had any.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    berries::{Ripeness, Ripening},
+    config::Config,
+    gameplay::{Ate, Damaged},
+    DeathEvent, Mongoose, Occupancy, Rat, Segmented, ShrinkEvent, Snake,
+};
+
+/// How a species gets hungry. Hunger goes from 0 when full to 1 when starving.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Appetite {
+    pub hunger_rate: f32,       // Hunger gained per second
+    pub satiety: f32,           // Below this much hunger, food doesn't interest it
+    pub starvation_period: f32, // How long a starving creature goes between losing segments
+}
+impl Default for Appetite {
+    fn default() -> Self {
+        Appetite {
+            hunger_rate: 0.02,
+            satiety: 0.2,
+            starvation_period: 10.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Metabolism {
+    pub hunger: f32,
+    rate: f32,
+    satiety: f32,
+    starvation: Timer,
+}
+impl Metabolism {
+    pub fn new(appetite: &Appetite) -> Metabolism {
+        Metabolism {
+            hunger: 0.0,
+            rate: appetite.hunger_rate,
+            satiety: appetite.satiety,
+            starvation: Timer::from_seconds(appetite.starvation_period, TimerMode::Repeating),
+        }
+    }
+    pub fn satiated(&self) -> bool {
+        self.hunger < self.satiety
+    }
+    pub fn starving(&self) -> bool {
+        self.hunger >= 1.0
+    }
+}
+
+/// Makes everything hungrier, and starving things shrink or die.
+#[allow(clippy::type_complexity)]
+pub fn digest(
+    mut creatures: Query<(
+        Entity,
+        &mut Metabolism,
+        Has<Segmented>,
+        Has<Mongoose>,
+        Has<Rat>,
+        Has<Snake>,
+    )>,
+    mut shrinks: EventWriter<ShrinkEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut damaged: EventWriter<Damaged>,
+    time: Res<Time>,
+) {
+    for (entity, mut metabolism, segmented, is_mongoose, is_rat, is_snake) in &mut creatures {
+        metabolism.hunger = (metabolism.hunger + metabolism.rate * time.delta_seconds()).min(1.0);
+        if !metabolism.starving() {
+            metabolism.starvation.reset();
+            continue;
+        }
+        if !metabolism.starvation.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let what = if is_mongoose {
+            Occupancy::Mongoose(entity)
+        } else if is_rat {
+            Occupancy::Rat(entity)
+        } else if is_snake {
+            Occupancy::Snake(entity)
+        } else {
+            continue;
+        };
+        debug!(target: "combat", ?what, "Starving");
+        if segmented {
+            shrinks.send(ShrinkEvent {
+                segmented: entity,
+                by: None,
+            });
+            damaged.send(Damaged { what, by: None });
+        } else {
+            deaths.send(DeathEvent { entity, by: None });
+        }
+    }
+}
+
+/// Takes what was eaten off the eater's hunger. This has to run before what was eaten is
+/// despawned, for berries to still say how ripe they were.
+pub fn feed(
+    mut ate: EventReader<Ate>,
+    mut metabolisms: Query<&mut Metabolism>,
+    berries: Query<&Ripening>,
+    config: Res<Config>,
+) {
+    for event in ate.read() {
+        let nutrition = match event.food {
+            Occupancy::Berry(berry) => {
+                let ripeness = berries.get(berry).map_or(Ripeness::Ripe, |r| r.ripeness);
+                config.berries.stage(ripeness).nutrition
+            }
+            Occupancy::Rat(_) => config.metabolism.rat_nutrition,
+            Occupancy::Mongoose(_)
+            | Occupancy::Snake(_)
+            | Occupancy::Bush(_)
+            | Occupancy::Wall(_) => continue,
+        };
+        if let Ok(mut metabolism) = metabolisms.get_mut(event.eater.entity()) {
+            metabolism.hunger = (metabolism.hunger - nutrition).max(0.0);
+            trace!(target: "combat", eater = ?event.eater, hunger = metabolism.hunger, "Fed");
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Breeding. Well-fed rats that meet have young, and well-fed snakes lay eggs that hatch into new
+//! snakes. Alongside the spawn timers, or instead of them, this keeps the arena populated. How it
+//! all goes is set in the config.
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    distance,
+    gameplay::Spawned,
+    metabolism::Metabolism,
+    spawn_rat, spawn_snake,
+    sprites::{ground_sprite, SpriteSheets},
+    Arena, GameRng, Position, Rat, Segmented, Snake,
+};
+
+const NEIGHBOURS: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+const EGG_COLOR: Color = Color::ANTIQUE_WHITE;
+const EGG_SIZE: f32 = 0.4; // Of a tile
+const EGG_Z: f32 = -0.4; // Under the creatures, which are at 0
+
+/// When a species breeds. Hunger goes from 0 when full to 1 when starving.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Breeding {
+    pub max_hunger: f32,       // Only creatures less hungry than this breed
+    pub cooldown: f32,         // Seconds between litters or eggs, and before the young can breed
+    pub hunger_cost: f32,      // Hunger added by breeding
+    pub max_population: usize, // Nothing more is bred once there are this many
+}
+impl Default for Breeding {
+    fn default() -> Self {
+        Breeding {
+            max_hunger: 0.3,
+            cooldown: 20.0,
+            hunger_cost: 0.3,
+            max_population: 10,
+        }
+    }
+}
+
+/// How long until a creature can breed again.
+#[derive(Component)]
+pub struct Fertility(Timer);
+impl Fertility {
+    pub fn new(breeding: &Breeding) -> Fertility {
+        Fertility(Timer::from_seconds(breeding.cooldown, TimerMode::Once))
+    }
+}
+
+#[derive(Component)]
+pub struct Egg(Timer); // Hatches when this finishes
+
+/// Finds an empty cell in the arena next to `position`.
+pub fn free_neighbour(position: &Position, arena: &Arena, rng: &mut impl Rng) -> Option<Position> {
+    let mut neighbours = NEIGHBOURS
+        .iter()
+        .map(|(dx, dy)| Position {
+            x: position.x + dx,
+            y: position.y + dy,
+        })
+        .filter(|p| !p.outside_arena() && !arena.isset(p.x, p.y))
+        .collect::<Vec<_>>();
+    if neighbours.is_empty() {
+        return None;
+    }
+    Some(neighbours.swap_remove(rng.gen_range(0..neighbours.len())))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn breed_rats(
+    mut commands: Commands,
+    mut rats: Query<(&Position, &mut Metabolism, &mut Fertility), With<Rat>>,
+    mut arena: ResMut<Arena>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut game_rng: ResMut<GameRng>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
+    time: Res<Time>,
+) {
+    let breeding = &config.reproduction.rat;
+    let mut population = rats.iter().count();
+    let mut ready = Vec::new();
+    for (position, metabolism, mut fertility) in &mut rats {
+        if fertility.0.tick(time.delta()).finished() && metabolism.hunger < breeding.max_hunger {
+            ready.push(*position);
+        }
+    }
+    let mut bred = Vec::new();
+    for (a, b) in ready.iter().tuple_combinations() {
+        if population >= breeding.max_population {
+            break;
+        }
+        if bred.contains(a)
+            || bred.contains(b)
+            || distance(a, b) > config.reproduction.rat_mating_range
+        {
+            continue;
+        }
+        let rng = &mut game_rng.rng;
+        let Some(nest) = free_neighbour(a, &arena, rng).or_else(|| free_neighbour(b, &arena, rng))
+        else {
+            continue;
+        };
+        let young = spawn_rat(
+            &mut commands,
+            &mut arena,
+            &sprite_sheets,
+            &config,
+            &mut spawned,
+            nest,
+        );
+        debug!(target: "spawn", parents = ?(a, b), ?young, "Rats bred");
+        bred.extend([*a, *b]);
+        population += 1;
+    }
+    for (position, mut metabolism, mut fertility) in &mut rats {
+        if bred.contains(position) {
+            metabolism.hunger = (metabolism.hunger + breeding.hunger_cost).min(1.0);
+            fertility.0.reset();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn lay_eggs(
+    mut commands: Commands,
+    mut snakes: Query<(Entity, &Segmented, &mut Metabolism, &mut Fertility), With<Snake>>,
+    eggs: Query<&Position, With<Egg>>,
+    positions: Query<&Position>,
+    config: Res<Config>,
+    time: Res<Time>,
+) {
+    let breeding = &config.reproduction.snake;
+    let mut population = snakes.iter().count() + eggs.iter().count();
+    for (snake, segmented, mut metabolism, mut fertility) in &mut snakes {
+        if !fertility.0.tick(time.delta()).finished() || metabolism.hunger >= breeding.max_hunger {
+            continue;
+        }
+        if population >= breeding.max_population {
+            continue;
+        }
+        // The egg is left where the tail is, to be uncovered as the snake moves on
+        let Some(tail) = segmented
+            .segments
+            .last()
+            .and_then(|tail| positions.get(*tail).ok())
+        else {
+            continue;
+        };
+        if tail.outside_arena() || eggs.iter().any(|egg| egg == tail) {
+            continue;
+        }
+        commands.spawn((
+            ground_sprite(EGG_COLOR, EGG_SIZE, EGG_Z),
+            Egg(Timer::from_seconds(
+                config.reproduction.hatch_time,
+                TimerMode::Once,
+            )),
+            *tail,
+        ));
+        debug!(target: "spawn", ?snake, position = ?tail, "Laid an egg");
+        metabolism.hunger = (metabolism.hunger + breeding.hunger_cost).min(1.0);
+        fertility.0.reset();
+        population += 1;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn hatch_eggs(
+    mut commands: Commands,
+    mut eggs: Query<(Entity, &Position, &mut Egg)>,
+    mut arena: ResMut<Arena>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut game_rng: ResMut<GameRng>,
+    config: Res<Config>,
+    mut spawned: EventWriter<Spawned>,
+    time: Res<Time>,
+) {
+    for (egg, position, mut timer) in &mut eggs {
+        if !timer.0.tick(time.delta()).finished() || arena.isset(position.x, position.y) {
+            continue;
+        }
+        // A hatchling is a head and a tail, so it needs a second empty cell to stretch out into
+        let Some(tail) = free_neighbour(position, &arena, &mut game_rng.rng) else {
+            continue;
+        };
+        let snake = spawn_snake(
+            &mut commands,
+            &mut arena,
+            &sprite_sheets,
+            &config,
+            &mut spawned,
+            position.x,
+            position.y,
+            0,
+            tail.x - position.x,
+            tail.y - position.y,
+        );
+        debug!(target: "spawn", ?egg, ?snake, "Egg hatched");
+        commands.entity(egg).despawn();
+    }
+}
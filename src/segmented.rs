@@ -0,0 +1,287 @@
+//! Segmented bodies, i.e. creatures made of a chain of segment entities like the mongoose and
+//! snakes.
+//!
+//! The `Segmented` component lives on a root entity, which has no `Position` of its own. Each
+//! segment is an entity with a `Position`, and every cell the body covers is registered in the
+//! `Arena` as the root's `Occupancy`.
+
+use bevy::{ecs::query::QueryFilter, prelude::*};
+use itertools::Itertools;
+
+use crate::{Arena, Occupancy, Position};
+
+pub const MIN_SEGMENTS: usize = 2; // A head and a tail; shrinking any further is fatal
+
+#[derive(Component)]
+pub struct Segmented {
+    pub head_position: Position,
+    pub segments: Vec<Entity>, // Head first, tail last
+    occupancy: Occupancy,
+}
+impl Segmented {
+    /// Spawns `length` segments in a line, starting with the head at `head_position` and trailing
+    /// away from it by `(delta_x, delta_y)` per segment. The returned component belongs on the
+    /// entity named in `occupancy`.
+    pub fn spawn<B: Bundle>(
+        commands: &mut Commands,
+        arena: &mut Arena,
+        occupancy: Occupancy,
+        head_position: Position,
+        length: usize,
+        (delta_x, delta_y): (i32, i32),
+        segment: impl Fn() -> B,
+    ) -> Segmented {
+        let cells = (0..length as i32)
+            .map(|i| Position {
+                x: head_position.x + i * delta_x,
+                y: head_position.y + i * delta_y,
+            })
+            .collect::<Vec<_>>();
+        Segmented::spawn_along(commands, arena, occupancy, &cells, segment)
+    }
+    /// Spawns a segment on each of `cells`, head first, each next to the one before it.
+    pub fn spawn_along<B: Bundle>(
+        commands: &mut Commands,
+        arena: &mut Arena,
+        occupancy: Occupancy,
+        cells: &[Position],
+        segment: impl Fn() -> B,
+    ) -> Segmented {
+        let mut segments = Vec::with_capacity(cells.len());
+        for position in cells.iter() {
+            segments.push(commands.spawn((segment(), *position)).id());
+            arena.set(position.x, position.y, occupancy);
+        }
+        Segmented {
+            head_position: cells[0],
+            segments,
+            occupancy,
+        }
+    }
+    /// Builds a body out of segments that already exist, e.g. ones severed by `split`, and
+    /// registers their cells.
+    #[allow(dead_code)] // Nothing splits yet
+    pub fn adopt(
+        segments: Vec<Entity>,
+        occupancy: Occupancy,
+        arena: &mut Arena,
+        positions: &Query<&Position>,
+    ) -> Segmented {
+        let head_position = *positions
+            .get(segments[0])
+            .expect("Adopted segment position missing");
+        let segmented = Segmented {
+            head_position,
+            segments,
+            occupancy,
+        };
+        for position in segmented.positions(positions).into_iter().dedup() {
+            arena.set(position.x, position.y, occupancy);
+        }
+        segmented
+    }
+    pub fn tail(&self) -> Entity {
+        *self
+            .segments
+            .last()
+            .expect("Segmented body has no segments")
+    }
+    /// Moves the head to `next_position` and drags every other segment into the place of the one
+    /// ahead of it. The cell left behind by the tail is freed, unless a freshly grown segment was
+    /// stacked on the tail to take it over.
+    pub fn advance<F: QueryFilter>(
+        &mut self,
+        next_position: Position,
+        arena: &mut Arena,
+        positions: &mut Query<&mut Position, F>,
+    ) {
+        self.head_position = next_position;
+        arena.set(next_position.x, next_position.y, self.occupancy);
+        let mut gap_position = next_position;
+        let mut grown = false;
+        for s in self.segments.iter() {
+            let mut position = positions.get_mut(*s).expect("Segment position missing");
+            (*position, gap_position) = (gap_position, *position);
+            if *position == gap_position {
+                grown = true;
+            }
+        }
+        if !grown {
+            arena.unset(gap_position.x, gap_position.y);
+        }
+    }
+    /// Stacks a new segment on the tail. It stays put on the next `advance` while the rest of the
+    /// body moves on.
+    pub fn grow<B: Bundle>(
+        &mut self,
+        commands: &mut Commands,
+        positions: &Query<&Position>,
+        segment: B,
+    ) -> Entity {
+        let tail_position = *positions
+            .get(self.tail())
+            .expect("Tail segment position missing");
+        let new_segment = commands.spawn((segment, tail_position)).id();
+        self.segments.push(new_segment);
+        new_segment
+    }
+    /// Despawns the tail segment and frees its cell. Returns `None`, changing nothing, if the body
+    /// is already down to `MIN_SEGMENTS`.
+    pub fn shrink(
+        &mut self,
+        commands: &mut Commands,
+        arena: &mut Arena,
+        positions: &Query<&Position>,
+    ) -> Option<Entity> {
+        if self.segments.len() <= MIN_SEGMENTS {
+            return None;
+        }
+        let tail = self.segments.pop().unwrap();
+        let tail_position = *positions.get(tail).expect("Tail segment position missing");
+        if *positions.get(self.tail()).unwrap() != tail_position {
+            // Unless the tail was stacked on the segment ahead of it, i.e. growth just occured
+            arena.unset(tail_position.x, tail_position.y);
+        }
+        commands.entity(tail).despawn();
+        Some(tail)
+    }
+    /// Cuts off every segment from index `at` onward and frees their cells. The severed segments
+    /// are returned, head end first, to be despawned or given to a new body with `adopt`.
+    #[allow(dead_code)] // Nothing splits yet
+    pub fn split(
+        &mut self,
+        at: usize,
+        arena: &mut Arena,
+        positions: &Query<&Position>,
+    ) -> Vec<Entity> {
+        let severed = self.segments.split_off(at);
+        let kept = self.positions(positions);
+        for s in severed.iter() {
+            let position = positions.get(*s).expect("Segment position missing");
+            if !kept.contains(position) && arena.occ(position.x, position.y) == Some(self.occupancy)
+            {
+                arena.unset(position.x, position.y);
+            }
+        }
+        severed
+    }
+    /// Turns the body around so that the tail leads. Every cell stays registered as it was.
+    #[allow(dead_code)] // Nothing turns around yet
+    pub fn reverse(&mut self, positions: &Query<&Position>) {
+        self.segments.reverse();
+        self.head_position = *positions
+            .get(self.segments[0])
+            .expect("Segment position missing");
+    }
+    /// The way the head last moved, from the segment behind it to the head. `None` while the two
+    /// are stacked, i.e. right after growing.
+    pub fn facing(&self, positions: &Query<&Position>) -> Option<Vec2> {
+        let neck = positions.get(*self.segments.get(1)?).ok()?;
+        let (x, y) = (self.head_position.x - neck.x, self.head_position.y - neck.y);
+        (x != 0 || y != 0).then(|| Vec2::new(x as f32, y as f32))
+    }
+    /// Frees every cell this body still holds.
+    pub fn vacate(&self, arena: &mut Arena, positions: &Query<&Position>) {
+        for position in self.positions(positions) {
+            if arena.occ(position.x, position.y) == Some(self.occupancy) {
+                arena.unset(position.x, position.y);
+            }
+        }
+    }
+    fn positions(&self, positions: &Query<&Position>) -> Vec<Position> {
+        self.segments
+            .iter()
+            .filter_map(|s| positions.get(*s).ok())
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// A world with a four segment snake lying along the bottom row, head on the left.
+    fn snake() -> (World, Entity) {
+        let mut world = World::new();
+        world.insert_resource(Arena::new());
+        let snake = world.spawn_empty().id();
+        let segmented =
+            world.run_system_once(move |mut commands: Commands, mut arena: ResMut<Arena>| {
+                let cells = (0..4).map(|x| Position { x, y: 0 }).collect::<Vec<_>>();
+                Segmented::spawn_along(
+                    &mut commands,
+                    &mut arena,
+                    Occupancy::Snake(snake),
+                    &cells,
+                    || (),
+                )
+            });
+        world.entity_mut(snake).insert(segmented);
+        (world, snake)
+    }
+
+    fn split(world: &mut World, at: usize) -> Vec<Entity> {
+        world.run_system_once(
+            move |mut snakes: Query<&mut Segmented>,
+                  mut arena: ResMut<Arena>,
+                  positions: Query<&Position>| {
+                snakes.single_mut().split(at, &mut arena, &positions)
+            },
+        )
+    }
+
+    fn occ(world: &World, x: i32) -> Option<Occupancy> {
+        world.resource::<Arena>().occ(x, 0)
+    }
+
+    #[test]
+    fn split_frees_the_severed_cells() {
+        let (mut world, snake) = snake();
+        let severed = split(&mut world, 2);
+        assert_eq!(severed.len(), 2);
+        assert_eq!(world.get::<Segmented>(snake).unwrap().segments.len(), 2);
+        assert_eq!(occ(&world, 1), Some(Occupancy::Snake(snake)));
+        assert_eq!(occ(&world, 2), None);
+        assert_eq!(occ(&world, 3), None);
+    }
+
+    #[test]
+    fn adopt_takes_over_severed_segments() {
+        let (mut world, _) = snake();
+        let severed = split(&mut world, 2);
+        let other = world.spawn_empty().id();
+        let adopted = world.run_system_once(
+            move |mut arena: ResMut<Arena>, positions: Query<&Position>| {
+                Segmented::adopt(
+                    severed.clone(),
+                    Occupancy::Snake(other),
+                    &mut arena,
+                    &positions,
+                )
+            },
+        );
+        assert_eq!(adopted.head_position, Position { x: 2, y: 0 });
+        assert_eq!(occ(&world, 2), Some(Occupancy::Snake(other)));
+        assert_eq!(occ(&world, 3), Some(Occupancy::Snake(other)));
+    }
+
+    #[test]
+    fn reverse_puts_the_head_at_the_tail() {
+        let (mut world, snake) = snake();
+        let segments = world.get::<Segmented>(snake).unwrap().segments.clone();
+        world.run_system_once(
+            |mut snakes: Query<&mut Segmented>, positions: Query<&Position>| {
+                snakes.single_mut().reverse(&positions);
+            },
+        );
+        let segmented = world.get::<Segmented>(snake).unwrap();
+        assert_eq!(segmented.head_position, Position { x: 3, y: 0 });
+        assert!(segmented.segments.iter().eq(segments.iter().rev()));
+        for x in 0..4 {
+            assert_eq!(occ(&world, x), Some(Occupancy::Snake(snake)));
+        }
+    }
+}
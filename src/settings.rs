@@ -0,0 +1,67 @@
+//! Player settings, as opposed to the per-mode `Config`. They are kept in a local RON file and
+//! saved whenever they change.
+
+use std::{fs, io};
+
+use bevy::{prelude::*, window::WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::input::Bindings;
+
+pub const SETTINGS_PATH: &str = "settings.ron";
+
+#[derive(Resource, Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(skip)]
+    path: String,
+    pub fullscreen: bool,
+    pub follow_mongoose: bool,
+    pub bindings: Bindings,
+    pub event_log_dir: Option<String>, // Where to write each game's gameplay events, if anywhere
+}
+impl Settings {
+    /// Reads the settings at `path`, falling back to the defaults if there are none yet or they
+    /// can't be read.
+    pub fn load(path: &str) -> Settings {
+        let mut settings = match fs::read_to_string(path) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+                warn!(target: "config", "Couldn't parse settings {}, using defaults: {}", path, e);
+                Settings::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Settings::default(),
+            Err(e) => {
+                warn!(target: "config", "Couldn't read settings {}, using defaults: {}", path, e);
+                Settings::default()
+            }
+        };
+        settings.path = path.into();
+        settings
+    }
+    fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(e) = fs::write(&self.path, contents) {
+                    warn!(target: "config", "Couldn't write settings {}: {}", self.path, e);
+                }
+            }
+            Err(e) => warn!(target: "config", "Couldn't serialize settings: {}", e),
+        }
+    }
+}
+
+pub fn apply_settings(settings: Res<Settings>, mut windows: Query<&mut Window>) {
+    if !settings.is_changed() {
+        return;
+    }
+    if !settings.is_added() {
+        settings.save();
+    }
+    for mut window in &mut windows {
+        window.mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+    }
+}
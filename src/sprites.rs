@@ -0,0 +1,245 @@
+//! The sprite sheets, loaded once at startup and shared by every spawner, so that spawning doesn't
+//! create new assets.
+
+use bevy::prelude::*;
+
+use crate::{
+    animation::{Animation, Sheet, SheetAnimations, ANIMATIONS_DIR},
+    SPRITE_SHEET_COLUMNS, TILE_SIZE,
+};
+
+pub struct SpriteSheet {
+    pub sheet: Sheet,
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub animations: SheetAnimations,
+}
+impl SpriteSheet {
+    /// Loads `name`.png and its animations from `name`.ron.
+    fn load(
+        asset_server: &AssetServer,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+        sheet: Sheet,
+        name: &str,
+    ) -> SpriteSheet {
+        let animations = SheetAnimations::load(&format!("{}/{}.ron", ANIMATIONS_DIR, name));
+        SpriteSheet {
+            sheet,
+            texture: asset_server.load(format!("{}.png", name)),
+            layout: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+                TILE_SIZE,
+                SPRITE_SHEET_COLUMNS,
+                animations.rows(),
+                None,
+                None,
+            )),
+            animations,
+        }
+    }
+    /// An animated sprite showing the first image on the sheet.
+    pub fn sprite(&self) -> (SpriteBundle, TextureAtlas, Animation) {
+        (
+            SpriteBundle {
+                texture: self.texture.clone(),
+                ..default()
+            },
+            TextureAtlas {
+                layout: self.layout.clone(),
+                ..default()
+            },
+            Animation::new(self.sheet),
+        )
+    }
+}
+
+/// A coloured square on the ground, for the things that don't have a sprite sheet.
+pub fn ground_sprite(color: Color, size: f32, z: f32) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(TILE_SIZE * size),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, z),
+        ..default()
+    }
+}
+
+#[derive(Resource)]
+pub struct SpriteSheets {
+    pub berry: SpriteSheet,
+    pub mongoose: SpriteSheet,
+    pub rat: SpriteSheet,
+    pub snake: SpriteSheet,
+}
+impl SpriteSheets {
+    pub fn get(&self, sheet: Sheet) -> &SpriteSheet {
+        match sheet {
+            Sheet::Berry => &self.berry,
+            Sheet::Mongoose => &self.mongoose,
+            Sheet::Rat => &self.rat,
+            Sheet::Snake => &self.snake,
+        }
+    }
+}
+
+pub fn load_sprite_sheets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let mut load =
+        |sheet, name| SpriteSheet::load(&asset_server, &mut texture_atlas_layouts, sheet, name);
+    commands.insert_resource(SpriteSheets {
+        berry: load(Sheet::Berry, "berry"),
+        mongoose: load(Sheet::Mongoose, "mongoose"),
+        rat: load(Sheet::Rat, "rat"),
+        snake: load(Sheet::Snake, "snake"),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::LoadState, render::texture::ImagePlugin};
+
+    use super::*;
+    use crate::{
+        config::Config, gameplay::Spawned, grow_segmented, spawn_berry, spawn_rat, spawn_snake,
+        teardown_game, Arena, GrowEvent, Position,
+    };
+
+    const CYCLES: usize = 1000;
+    const MAX_LOADING_UPDATES: usize = 10_000;
+
+    /// Spawns one of everything that has a sprite sheet, and has the snake grow a segment.
+    fn spawn_everything(
+        mut commands: Commands,
+        mut arena: ResMut<Arena>,
+        sprite_sheets: Res<SpriteSheets>,
+        config: Res<Config>,
+        mut spawned: EventWriter<Spawned>,
+        mut grow: EventWriter<GrowEvent>,
+    ) {
+        let p = |x, y| Position { x, y };
+        spawn_berry(
+            &mut commands,
+            &mut arena,
+            &sprite_sheets,
+            &mut spawned,
+            p(1, 1),
+        );
+        spawn_rat(
+            &mut commands,
+            &mut arena,
+            &sprite_sheets,
+            &config,
+            &mut spawned,
+            p(3, 1),
+        );
+        let snake = spawn_snake(
+            &mut commands,
+            &mut arena,
+            &sprite_sheets,
+            &config,
+            &mut spawned,
+            5,
+            5,
+            1,
+            1,
+            0,
+        );
+        grow.send(GrowEvent { segmented: snake });
+    }
+
+    /// Checks that every sprite shows a sheet's own texture and layout, not a copy.
+    fn check_handles(
+        sprites: Query<(&Handle<Image>, &TextureAtlas)>,
+        sprite_sheets: Res<SpriteSheets>,
+    ) {
+        let sheets = [Sheet::Berry, Sheet::Mongoose, Sheet::Rat, Sheet::Snake]
+            .map(|sheet| sprite_sheets.get(sheet));
+        for (texture, texture_atlas) in &sprites {
+            assert!(sheets.iter().any(|sheet| sheet.texture == *texture));
+            assert!(sheets
+                .iter()
+                .any(|sheet| sheet.layout == texture_atlas.layout));
+        }
+    }
+
+    fn clear_arena(mut commands: Commands) {
+        commands.insert_resource(Arena::new());
+    }
+
+    fn asset_counts(app: &App) -> (usize, usize) {
+        (
+            app.world.resource::<Assets<Image>>().len(),
+            app.world.resource::<Assets<TextureAtlasLayout>>().len(),
+        )
+    }
+
+    #[test]
+    fn asset_counts_stay_flat() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            ImagePlugin::default(),
+        ))
+        .init_asset::<TextureAtlasLayout>()
+        .add_event::<Spawned>()
+        .add_event::<GrowEvent>()
+        .insert_resource(Arena::new())
+        .init_resource::<Config>()
+        .add_systems(Startup, load_sprite_sheets)
+        .add_systems(
+            Update,
+            (
+                spawn_everything,
+                grow_segmented,
+                check_handles,
+                teardown_game,
+                clear_arena,
+            )
+                .chain(),
+        );
+        // Registers the image loader, which running the app would do
+        app.finish();
+        app.cleanup();
+
+        // Let the sheets finish loading, so that nothing still on its way is counted later
+        app.update();
+        let textures = [Sheet::Berry, Sheet::Mongoose, Sheet::Rat, Sheet::Snake]
+            .map(|sheet| app.world.resource::<SpriteSheets>().get(sheet).texture.id());
+        let loaded = |app: &App| {
+            let asset_server = app.world.resource::<AssetServer>();
+            textures
+                .iter()
+                .map(|texture| asset_server.get_load_state(*texture))
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..MAX_LOADING_UPDATES {
+            if loaded(&app)
+                .iter()
+                .all(|state| *state == Some(LoadState::Loaded))
+            {
+                break;
+            }
+            std::thread::yield_now();
+            app.update();
+        }
+        assert!(
+            loaded(&app)
+                .iter()
+                .all(|state| *state == Some(LoadState::Loaded)),
+            "Sheets didn't load: {:?}",
+            loaded(&app)
+        );
+
+        let before = asset_counts(&app);
+        assert!(before.0 >= textures.len() && before.1 == textures.len());
+        for _ in 0..CYCLES {
+            app.update();
+        }
+        assert_eq!(asset_counts(&app), before);
+    }
+}
@@ -0,0 +1,67 @@
+//! Smooth movement between tiles. A creature's `Position` still jumps a whole tile at a time; its
+//! `Tween` remembers where it came from so that `transformation` can slide the sprite across over
+//! the creature's movement period instead of snapping it there.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::Position;
+
+#[derive(Component)]
+pub struct Tween {
+    from: Option<Position>,
+    to: Option<Position>,
+    elapsed: f32,
+    period: f32, // How long one step between tiles takes to play out
+    turn: f32,   // Angle the sprite starts rotated by after changing direction, easing to zero
+}
+impl Tween {
+    pub fn new(period: f32) -> Tween {
+        Tween {
+            from: None,
+            to: None,
+            elapsed: 0.0,
+            period,
+            turn: 0.0,
+        }
+    }
+    /// Starts the step to `position`. Anything further than one tile away is jumped to directly.
+    fn start(&mut self, position: Position) {
+        let adjacent = self
+            .to
+            .is_some_and(|to| (to.x - position.x).abs() + (to.y - position.y).abs() == 1);
+        self.from = if adjacent { self.to } else { Some(position) };
+        self.to = Some(position);
+        self.elapsed = 0.0;
+        self.turn = 0.0;
+    }
+    /// Rotates the sprite back by a quarter turn, so that it swings round to face its new
+    /// direction during this step. `clockwise` is the direction of the turn being made.
+    pub fn turn(&mut self, clockwise: bool) {
+        self.turn = if clockwise { FRAC_PI_2 } else { -FRAC_PI_2 };
+    }
+    /// Moves the step on by `delta` seconds. Returns where the step started, where it ends and how
+    /// far along it is, from 0 to 1.
+    pub fn tick(&mut self, delta: f32) -> Option<(Position, Position, f32)> {
+        self.elapsed += delta;
+        let t = (self.elapsed / self.period).min(1.0);
+        Some((self.from?, self.to?, t))
+    }
+    /// Whether a step is still playing out.
+    pub fn moving(&self) -> bool {
+        self.from != self.to && self.elapsed < self.period
+    }
+    pub fn rotation(&self) -> Quat {
+        let t = (self.elapsed / self.period).min(1.0);
+        Quat::from_rotation_z(self.turn * (1.0 - t))
+    }
+}
+
+pub fn start_tweens(mut tweens: Query<(&Position, &mut Tween), Changed<Position>>) {
+    for (position, mut tween) in &mut tweens {
+        if tween.to != Some(*position) {
+            tween.start(*position);
+        }
+    }
+}
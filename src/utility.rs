@@ -0,0 +1,175 @@
+//! Utility AI. Whenever a creature decides what to do next, every choice open to it is scored from
+//! how far away it is, how hungry and how threatened the creature is, and how much its species
+//! likes it. The creature then takes the best, or samples one by softmax so that it doesn't always
+//! do the obvious thing. The weights are read from a RON file per species in `UTILITY_DIR`.
+
+use std::fs;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{distance, Position, Target};
+
+pub const UTILITY_DIR: &str = "assets/ai";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UtilityWeights {
+    pub berry: f32, // How much eating berries appeals
+    pub rat: f32,   // How much hunting rats appeals
+    pub wander: f32,
+    pub rest: f32,            // Less appealing the hungrier the creature is
+    pub flee: f32,            // Scaled by how close the nearest predator is
+    pub hunger: f32,          // How much more appealing food is when starving than when full
+    pub falloff: f32,         // How quickly things lose their appeal with distance
+    pub fear_radius: i32,     // How close a predator has to be before it's a threat
+    pub exit_preference: i32, // How much safer a way out of the arena counts as being when fleeing
+    pub temperature: f32,     // Softmax temperature, or 0 to always take the best choice
+}
+impl Default for UtilityWeights {
+    fn default() -> Self {
+        UtilityWeights {
+            berry: 1.0,
+            rat: 0.0,
+            wander: 1.0,
+            rest: 1.0,
+            flee: 0.0,
+            hunger: 1.0,
+            falloff: 0.2,
+            fear_radius: 4,
+            exit_preference: 0,
+            temperature: 0.0,
+        }
+    }
+}
+impl UtilityWeights {
+    /// Reads the weights at `path`. A missing or broken file leaves the species with the defaults.
+    pub fn load(path: &str) -> UtilityWeights {
+        match fs::read_to_string(path) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+                warn!(target: "ai", "Couldn't parse utility weights {}, using defaults: {}", path, e);
+                UtilityWeights::default()
+            }),
+            Err(e) => {
+                warn!(target: "ai", "Couldn't read utility weights {}, using defaults: {}", path, e);
+                UtilityWeights::default()
+            }
+        }
+    }
+    /// How threatening a predator at distance `nearest` is, from 0 outside `fear_radius` to 1 when
+    /// it's adjacent.
+    pub fn threat(&self, nearest: Option<i32>) -> f32 {
+        match nearest {
+            Some(d) if d <= self.fear_radius => {
+                1.0 - (d - 1).max(0) as f32 / self.fear_radius.max(1) as f32
+            }
+            _ => 0.0,
+        }
+    }
+    fn proximity(&self, from: &Position, to: &Position) -> f32 {
+        1.0 / (1.0 + self.falloff * distance(from, to) as f32)
+    }
+    /// Every choice worth considering, with its utility.
+    pub fn score(&self, surroundings: &Surroundings) -> Vec<(Choice, f32)> {
+        let Surroundings {
+            position, hunger, ..
+        } = surroundings;
+        let appetite = 1.0 + self.hunger * hunger;
+        let mut choices = Vec::new();
+        for (weight, food) in [
+            (self.berry, surroundings.berries),
+            (self.rat, surroundings.rats),
+        ] {
+            if weight <= 0.0 {
+                continue;
+            }
+            choices.extend(food.iter().map(|&(entity, p)| {
+                (
+                    Choice::Eat(entity),
+                    weight * appetite * self.proximity(position, &p),
+                )
+            }));
+        }
+        if let Some(p) = surroundings.wander {
+            choices.push((Choice::Wander(p), self.wander));
+        }
+        if let Some(p) = surroundings.flee {
+            let threat = self.threat(surroundings.nearest_predator);
+            choices.push((Choice::Flee(p), self.flee * threat));
+        }
+        choices.push((Choice::Rest, self.rest * (1.0 - hunger)));
+        choices.retain(|(_, utility)| *utility > 0.0);
+        choices
+    }
+    /// Scores the choices and picks one.
+    pub fn choose(&self, surroundings: &Surroundings, rng: &mut impl Rng) -> Choice {
+        let choices = self.score(surroundings);
+        trace!(target: "ai", ?choices, "Scored choices");
+        if self.temperature <= 0.0 {
+            return choices
+                .iter()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(Choice::Rest, |(choice, _)| *choice);
+        }
+        // Subtracting the best utility first keeps the exponentials from overflowing
+        let best = choices
+            .iter()
+            .map(|(_, utility)| *utility)
+            .fold(0.0, f32::max);
+        let weights = choices
+            .iter()
+            .map(|(_, utility)| ((utility - best) / self.temperature).exp())
+            .collect::<Vec<_>>();
+        let mut roll = rng.gen_range(0.0..weights.iter().sum::<f32>().max(f32::MIN_POSITIVE));
+        for ((choice, _), weight) in choices.iter().zip(weights) {
+            if roll < weight {
+                return *choice;
+            }
+            roll -= weight;
+        }
+        choices.last().map_or(Choice::Rest, |(choice, _)| *choice)
+    }
+}
+
+#[derive(Resource)]
+pub struct Utilities {
+    pub rat: UtilityWeights,
+    pub snake: UtilityWeights,
+}
+impl Utilities {
+    pub fn load(dir: &str) -> Utilities {
+        Utilities {
+            rat: UtilityWeights::load(&format!("{}/rat.ron", dir)),
+            snake: UtilityWeights::load(&format!("{}/snake.ron", dir)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Choice {
+    Eat(Entity),
+    Wander(Position),
+    Flee(Position),
+    Rest,
+}
+impl Choice {
+    pub fn target(&self) -> Option<Target> {
+        match *self {
+            Choice::Eat(entity) => Some(Target::Entity(entity)),
+            Choice::Wander(position) | Choice::Flee(position) => Some(Target::Position(position)),
+            Choice::Rest => None,
+        }
+    }
+}
+
+/// What a creature knows when it decides. Food is only listed if it's within reach.
+pub struct Surroundings<'a> {
+    pub position: Position,
+    pub hunger: f32,
+    pub berries: &'a [(Entity, Position)],
+    pub rats: &'a [(Entity, Position)],
+    pub nearest_predator: Option<i32>,
+    pub wander: Option<Position>, // Somewhere to wander to, if anywhere was found
+    pub flee: Option<Position>,   // Somewhere safer, if threatened
+}
@@ -0,0 +1,203 @@
+//! What creatures can see. Each species sees so many cells as the crow flies, within a cone around
+//! the way it's facing, and cells it's right next to whichever way it faces. Walls, bushes and tall
+//! grass block sight: they can be seen themselves, but not past, which symmetric shadowcasting
+//! works out for every cell in range at once. Whatever is in tall grass is hidden from anything that
+//! isn't right next to it.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{level::Terrain, Arena, Occupancy, Position, ARENA_HEIGHT, ARENA_WIDTH};
+
+const NEARBY: f32 = 1.5; // Cells within this distance are always seen, diagonal neighbours included
+
+// Each quadrant as the directions of its rows and of the columns along a row
+const QUADRANTS: [((i32, i32), (i32, i32)); 4] = [
+    ((0, 1), (1, 0)),
+    ((0, -1), (1, 0)),
+    ((1, 0), (0, 1)),
+    ((-1, 0), (0, 1)),
+];
+
+/// How a species sees.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Vision {
+    pub range: f32, // Cells, as the crow flies
+    pub cone: f32,  // Degrees, centred on the way it's facing; 360 to see all round
+}
+impl Default for Vision {
+    fn default() -> Self {
+        Vision {
+            range: 8.0,
+            cone: 360.0,
+        }
+    }
+}
+
+/// The way a creature without a `Segmented` body last moved.
+#[derive(Component, Clone, Copy, Default)]
+pub struct Facing {
+    pub x: i32,
+    pub y: i32,
+}
+impl Facing {
+    pub fn towards(from: &Position, to: &Position) -> Facing {
+        Facing {
+            x: (to.x - from.x).signum(),
+            y: (to.y - from.y).signum(),
+        }
+    }
+    /// `None` until it has moved at all.
+    pub fn direction(&self) -> Option<Vec2> {
+        (self.x != 0 || self.y != 0).then(|| Vec2::new(self.x as f32, self.y as f32))
+    }
+}
+
+/// Straight-line distance between the centres of two cells.
+pub fn euclidean(a: &Position, b: &Position) -> f32 {
+    (((a.x - b.x).pow(2) + (a.y - b.y).pow(2)) as f32).sqrt()
+}
+
+/// The cells on the line from `from` to `to`, both included.
+pub fn line(from: &Position, to: &Position) -> Vec<Position> {
+    let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+    let (step_x, step_y) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut error = dx + dy;
+    let mut cell = *from;
+    let mut cells = vec![cell];
+    while cell != *to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            cell.x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            cell.y += step_y;
+        }
+        cells.push(cell);
+    }
+    cells
+}
+
+/// The arena plus the ring of cells just outside it, where creatures escape.
+fn in_bounds(p: &Position) -> bool {
+    p.x >= -1 && p.x <= ARENA_WIDTH && p.y >= -1 && p.y <= ARENA_HEIGHT
+}
+
+fn opaque(arena: &Arena, terrain: &Terrain, p: &Position) -> bool {
+    !in_bounds(p)
+        || terrain.grass(p)
+        || matches!(
+            arena.occ(p.x, p.y),
+            Some(Occupancy::Wall(_) | Occupancy::Bush(_))
+        )
+}
+
+/// A row of a quadrant being scanned, between two slopes measured from the middle of the row.
+struct Row {
+    depth: i32,
+    start: f32,
+    end: f32,
+}
+impl Row {
+    fn columns(&self) -> std::ops::RangeInclusive<i32> {
+        let depth = self.depth as f32;
+        let min = (depth * self.start + 0.5).floor() as i32; // Rounding ties up
+        let max = (depth * self.end - 0.5).ceil() as i32; // Rounding ties down
+        min..=max
+    }
+    fn symmetric(&self, column: i32) -> bool {
+        let (depth, column) = (self.depth as f32, column as f32);
+        column >= depth * self.start && column <= depth * self.end
+    }
+}
+
+fn slope(depth: i32, column: i32) -> f32 {
+    (2 * column - 1) as f32 / (2 * depth) as f32
+}
+
+/// Everything one creature can see.
+pub struct Sight {
+    origin: Position,
+    cells: HashSet<Position>,
+}
+impl Sight {
+    /// What can be seen from `origin`, looking along `facing` if it's facing any way at all.
+    pub fn new(
+        arena: &Arena,
+        terrain: &Terrain,
+        origin: &Position,
+        facing: Option<Vec2>,
+        vision: &Vision,
+    ) -> Sight {
+        let mut cells = HashSet::from([*origin]);
+        let half_cone = (vision.cone / 2.0).to_radians();
+        let in_cone = |p: &Position| {
+            let offset = Vec2::new((p.x - origin.x) as f32, (p.y - origin.y) as f32);
+            match facing {
+                Some(facing) if vision.cone < 360.0 => {
+                    facing.angle_between(offset).abs() <= half_cone
+                }
+                _ => true,
+            }
+        };
+        for ((row_x, row_y), (column_x, column_y)) in QUADRANTS {
+            let cell = |depth: i32, column: i32| Position {
+                x: origin.x + depth * row_x + column * column_x,
+                y: origin.y + depth * row_y + column * column_y,
+            };
+            let mut rows = vec![Row {
+                depth: 1,
+                start: -1.0,
+                end: 1.0,
+            }];
+            while let Some(mut row) = rows.pop() {
+                if row.depth as f32 > vision.range.max(NEARBY) {
+                    continue;
+                }
+                let mut previous = None;
+                for column in row.columns() {
+                    let p = cell(row.depth, column);
+                    let blocked = opaque(arena, terrain, &p);
+                    let distance = euclidean(origin, &p);
+                    let in_view = distance <= NEARBY || (distance <= vision.range && in_cone(&p));
+                    if in_bounds(&p) && in_view && (blocked || row.symmetric(column)) {
+                        cells.insert(p);
+                    }
+                    match (previous, blocked) {
+                        (Some(true), false) => row.start = slope(row.depth, column),
+                        (Some(false), true) => rows.push(Row {
+                            depth: row.depth + 1,
+                            start: row.start,
+                            end: slope(row.depth, column),
+                        }),
+                        _ => {}
+                    }
+                    previous = Some(blocked);
+                }
+                if previous == Some(false) {
+                    rows.push(Row {
+                        depth: row.depth + 1,
+                        ..row
+                    });
+                }
+            }
+        }
+        Sight {
+            origin: *origin,
+            cells,
+        }
+    }
+    /// Whether the cell at `p` can be seen.
+    pub fn sees(&self, p: &Position) -> bool {
+        self.cells.contains(p)
+    }
+    /// Whether something at `p` can be seen, which in tall grass means being right next to it.
+    pub fn spots(&self, terrain: &Terrain, p: &Position) -> bool {
+        self.sees(p) && (!terrain.grass(p) || euclidean(&self.origin, p) <= NEARBY)
+    }
+}